@@ -16,6 +16,9 @@ use crate::ui::ImguiEditor;
 use crate::behaviours::{
     renderer_behaviour::RendererBehaviour,
     raycaster_behaviour::RaycasterBehaviour,
+    background_behaviour::BackgroundBehaviour,
+    fog_behaviour::FogBehaviour,
+    medium_behaviour::MediumBehaviour,
     camera_behaviour::CameraBehaviour,
     Behaviour
 };
@@ -49,6 +52,9 @@ pub struct App {
     raycaster_behaviour: RaycasterBehaviour,
     material_behaviour: MaterialBehaviour,
     lightning_behaviour: LightningBehaviour,
+    background_behaviour: BackgroundBehaviour,
+    fog_behaviour: FogBehaviour,
+    medium_behaviour: MediumBehaviour,
     
     is_running: bool,
     time_instant: Instant,
@@ -87,6 +93,9 @@ impl App {
         let raycaster_behaviour = RaycasterBehaviour::new(renderer.clone());
         let material_behaviour = MaterialBehaviour::new(renderer.clone());
         let lightning_behaviour = LightningBehaviour::new(renderer.clone());
+        let background_behaviour = BackgroundBehaviour::new(renderer.clone());
+        let fog_behaviour = FogBehaviour::new(renderer.clone());
+        let medium_behaviour = MediumBehaviour::new(renderer.clone());
 
         return Ok(App {
             _video: video,
@@ -104,6 +113,9 @@ impl App {
             raycaster_behaviour,
             material_behaviour,
             lightning_behaviour,
+            background_behaviour,
+            fog_behaviour,
+            medium_behaviour,
 
             is_running: true,
             time_instant: Instant::now(),
@@ -145,7 +157,20 @@ impl App {
             if ui.collapsing_header("Lightning", imgui::TreeNodeFlags::empty()) {
                 self.lightning_behaviour.draw_ui(ui);
             }
+
+            if ui.collapsing_header("Background", imgui::TreeNodeFlags::empty()) {
+                self.background_behaviour.draw_ui(ui);
+            }
+
+            if ui.collapsing_header("Fog", imgui::TreeNodeFlags::empty()) {
+                self.fog_behaviour.draw_ui(ui);
+            }
+
+            if ui.collapsing_header("Volumetrics", imgui::TreeNodeFlags::empty()) {
+                self.medium_behaviour.draw_ui(ui);
+            }
         });
+        self.material_behaviour.draw_graph_window(ui);
 
         self.renderer.borrow_mut().render();
         self.camera_behaviour.update(self.delta_time);
@@ -153,6 +178,9 @@ impl App {
         self.raycaster_behaviour.update(self.delta_time);
         self.material_behaviour.update(self.delta_time);
         self.lightning_behaviour.update(self.delta_time);
+        self.background_behaviour.update(self.delta_time);
+        self.fog_behaviour.update(self.delta_time);
+        self.medium_behaviour.update(self.delta_time);
 
         self.renderer.borrow().get_pixel_canvas().render(
             ui,
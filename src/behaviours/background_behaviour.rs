@@ -0,0 +1,262 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{utils::color::Color, rendering::{renderer::Renderer, background::{Background, DEFAULT_SUN_ANGLE, DEFAULT_SUN_INTENSITY}, environment::EnvironmentMap, cubemap::{Cubemap, CUBEMAP_FACE_NAMES}}, ui::ImguiEditor};
+
+use super::Behaviour;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum BackgroundType {
+    Solid, Gradient, Sky, Skybox, Environment
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum SkyboxLayout {
+    Faces, Cross
+}
+
+pub struct BackgroundBehaviour {
+    renderer: Rc<RefCell<Renderer>>,
+    background_type: BackgroundType,
+    solid_color: Color,
+    zenith_color: Color,
+    horizon_color: Color,
+    ground_color: Color,
+    sun_color: Color,
+    sun_angle: f32,
+    sun_intensity: f32,
+    skybox_layout: SkyboxLayout,
+    skybox_face_paths: [String; 6],
+    skybox_cross_path: String,
+    skybox: Option<Rc<Cubemap>>,
+    skybox_error: Option<String>,
+    environment_path: String,
+    environment_intensity: f32,
+    environment_error: Option<String>
+}
+
+impl BackgroundBehaviour {
+    pub fn new(renderer: Rc<RefCell<Renderer>>) -> BackgroundBehaviour {
+        let mut behaviour = BackgroundBehaviour {
+            renderer,
+            background_type: BackgroundType::Solid,
+            solid_color: Color::new(0, 0, 0),
+            zenith_color: Color::new(40, 90, 200),
+            horizon_color: Color::new(170, 200, 235),
+            ground_color: Color::new(60, 55, 50),
+            sun_color: Color::new(255, 240, 210),
+            sun_angle: DEFAULT_SUN_ANGLE,
+            sun_intensity: DEFAULT_SUN_INTENSITY,
+            skybox_layout: SkyboxLayout::Faces,
+            skybox_face_paths: Default::default(),
+            skybox_cross_path: String::new(),
+            skybox: None,
+            skybox_error: None,
+            environment_path: String::new(),
+            environment_intensity: 1.0,
+            environment_error: None
+        };
+
+        behaviour.update_background();
+
+        behaviour
+    }
+
+    fn update_background(&mut self) {
+        let background = match self.background_type {
+            BackgroundType::Solid | BackgroundType::Environment => Background::Solid(self.solid_color),
+            BackgroundType::Gradient => Background::Gradient {
+                horizon_color: self.horizon_color,
+                zenith_color: self.zenith_color
+            },
+            BackgroundType::Sky => Background::Sky {
+                zenith_color: self.zenith_color,
+                horizon_color: self.horizon_color,
+                ground_color: self.ground_color,
+                sun_color: self.sun_color,
+                sun_angle: self.sun_angle,
+                sun_intensity: self.sun_intensity
+            },
+            BackgroundType::Skybox => match &self.skybox {
+                Some(cubemap) => Background::Skybox(cubemap.clone()),
+                None => Background::Solid(self.solid_color)
+            }
+        };
+        self.renderer.borrow_mut().set_background(background);
+    }
+
+    fn load_skybox(&mut self) {
+        let result = match self.skybox_layout {
+            SkyboxLayout::Faces => Cubemap::load_faces(&self.skybox_face_paths),
+            SkyboxLayout::Cross => Cubemap::load_cross(&self.skybox_cross_path)
+        };
+
+        match result {
+            Ok(cubemap) => {
+                self.skybox = Some(Rc::new(cubemap));
+                self.skybox_error = None;
+            },
+            Err(err) => {
+                self.skybox = None;
+                self.skybox_error = Some(err);
+            }
+        }
+    }
+
+    /// Returns true, when the skybox was (re)loaded.
+    fn draw_skybox_ui(&mut self, ui: &imgui::Ui) -> bool {
+        ui.radio_button("Six faces", &mut self.skybox_layout, SkyboxLayout::Faces);
+        ui.same_line();
+        ui.radio_button("Cross", &mut self.skybox_layout, SkyboxLayout::Cross);
+
+        match self.skybox_layout {
+            SkyboxLayout::Faces => {
+                for (path, name) in self.skybox_face_paths.iter_mut().zip(CUBEMAP_FACE_NAMES) {
+                    ui.input_text(name, path)
+                        .hint("path/to/face.png")
+                        .build();
+                }
+            },
+            SkyboxLayout::Cross => {
+                ui.input_text("Path", &mut self.skybox_cross_path)
+                    .hint("path/to/cross.png")
+                    .build();
+            }
+        };
+
+        let loaded = ui.button("Load");
+        if loaded {
+            self.load_skybox();
+        }
+
+        if let Some(err) = &self.skybox_error {
+            ui.text_colored([1.0, 0.3, 0.3, 1.0], err);
+        }
+
+        loaded
+    }
+
+    /// Load environment map from the current path, or remove it when the path is empty.
+    fn update_environment(&mut self) {
+        self.environment_error = None;
+
+        if self.background_type != BackgroundType::Environment || self.environment_path.is_empty() {
+            self.renderer.borrow_mut().set_environment(None);
+            return;
+        }
+
+        match EnvironmentMap::load(&self.environment_path) {
+            Ok(mut environment) => {
+                environment.set_intensity(self.environment_intensity);
+                self.renderer.borrow_mut().set_environment(Some(environment));
+            },
+            Err(err) => {
+                self.renderer.borrow_mut().set_environment(None);
+                self.environment_error = Some(err);
+            }
+        }
+    }
+
+    fn draw_environment_ui(&mut self, ui: &imgui::Ui) {
+        ui.input_text("Path", &mut self.environment_path)
+            .hint("path/to/image.hdr")
+            .build();
+
+        if ui.button("Load") {
+            self.update_environment();
+        }
+
+        if let Some(err) = &self.environment_error {
+            ui.text_colored([1.0, 0.3, 0.3, 1.0], err);
+        }
+
+        let has_environment = self.renderer.borrow().get_scene().get_environment().is_some();
+        ui.disabled(!has_environment, || {
+            let modified = imgui::Drag::new("Intensity")
+                .speed(0.01)
+                .range(0.0, f32::INFINITY)
+                .build(ui, &mut self.environment_intensity);
+
+            if modified {
+                if let Some(environment) = self.renderer.borrow_mut().get_mut_environment() {
+                    environment.set_intensity(self.environment_intensity);
+                }
+            }
+        });
+    }
+}
+
+impl Behaviour for BackgroundBehaviour {
+    fn update(&mut self, _delta_time: f32) {}
+}
+
+impl ImguiEditor for BackgroundBehaviour {
+    fn draw_ui(&mut self, ui: &imgui::Ui) {
+        let mut modified = false;
+
+        ui.text("Background type");
+        let mut type_modified = ui.radio_button(
+            "Solid",
+            &mut self.background_type,
+            BackgroundType::Solid
+        );
+        ui.same_line();
+        type_modified |= ui.radio_button(
+            "Gradient",
+            &mut self.background_type,
+            BackgroundType::Gradient
+        );
+        ui.same_line();
+        type_modified |= ui.radio_button(
+            "Sky",
+            &mut self.background_type,
+            BackgroundType::Sky
+        );
+        ui.same_line();
+        type_modified |= ui.radio_button(
+            "Skybox",
+            &mut self.background_type,
+            BackgroundType::Skybox
+        );
+        ui.same_line();
+        type_modified |= ui.radio_button(
+            "Environment",
+            &mut self.background_type,
+            BackgroundType::Environment
+        );
+
+        match self.background_type {
+            BackgroundType::Solid => {
+                modified |= ui.color_edit4("Color", &mut self.solid_color);
+            },
+            BackgroundType::Gradient => {
+                modified |= ui.color_edit4("Zenith color", &mut self.zenith_color);
+                modified |= ui.color_edit4("Horizon color", &mut self.horizon_color);
+            },
+            BackgroundType::Sky => {
+                modified |= ui.color_edit4("Zenith color", &mut self.zenith_color);
+                modified |= ui.color_edit4("Horizon color", &mut self.horizon_color);
+                modified |= ui.color_edit4("Ground color", &mut self.ground_color);
+                modified |= ui.color_edit4("Sun color", &mut self.sun_color);
+                modified |= ui.slider("Sun size", 0.1, 10.0, &mut self.sun_angle);
+                modified |= imgui::Drag::new("Sun intensity")
+                    .speed(0.1)
+                    .range(0.0, f32::INFINITY)
+                    .build(ui, &mut self.sun_intensity);
+                ui.text_disabled("Sun follows the first directional light");
+            },
+            BackgroundType::Skybox => {
+                modified |= self.draw_skybox_ui(ui);
+            },
+            BackgroundType::Environment => {
+                self.draw_environment_ui(ui);
+            }
+        };
+
+        if type_modified {
+            self.update_environment();
+        }
+        if modified || type_modified {
+            self.update_background();
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{rendering::{renderer::Renderer, fog::{Fog, FogFalloff, DEFAULT_FOG_DENSITY, DEFAULT_HEIGHT_FALLOFF}}, ui::ImguiEditor};
+
+use super::Behaviour;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum FogType {
+    None, Linear, Exponential, ExponentialSquared
+}
+
+pub struct FogBehaviour {
+    renderer: Rc<RefCell<Renderer>>,
+    fog_type: FogType,
+    start: f32,
+    end: f32,
+    density: f32,
+    is_height_fog: bool,
+    height_falloff: f32,
+    height: f32
+}
+
+impl FogBehaviour {
+    pub fn new(renderer: Rc<RefCell<Renderer>>) -> FogBehaviour {
+        let mut behaviour = FogBehaviour {
+            renderer,
+            fog_type: FogType::None,
+            start: 2.0,
+            end: 10.0,
+            density: DEFAULT_FOG_DENSITY,
+            is_height_fog: false,
+            height_falloff: DEFAULT_HEIGHT_FALLOFF,
+            height: -1.0
+        };
+
+        behaviour.update_fog();
+
+        behaviour
+    }
+
+    fn update_fog(&mut self) {
+        let falloff = match self.fog_type {
+            FogType::None => {
+                self.renderer.borrow_mut().set_fog(None);
+                return;
+            },
+            FogType::Linear => FogFalloff::Linear { start: self.start, end: self.end },
+            FogType::Exponential => FogFalloff::Exponential,
+            FogType::ExponentialSquared => FogFalloff::ExponentialSquared
+        };
+
+        let fog = Fog {
+            falloff,
+            density: self.density,
+            height_falloff: if self.is_height_fog { Some(self.height_falloff) } else { None },
+            height: self.height
+        };
+        self.renderer.borrow_mut().set_fog(Some(fog));
+    }
+}
+
+impl Behaviour for FogBehaviour {
+    fn update(&mut self, _delta_time: f32) {}
+}
+
+impl ImguiEditor for FogBehaviour {
+    fn draw_ui(&mut self, ui: &imgui::Ui) {
+        ui.text("Fog type");
+        let mut modified = ui.radio_button(
+            "None",
+            &mut self.fog_type,
+            FogType::None
+        );
+        ui.same_line();
+        modified |= ui.radio_button(
+            "Linear",
+            &mut self.fog_type,
+            FogType::Linear
+        );
+        ui.same_line();
+        modified |= ui.radio_button(
+            "Exp",
+            &mut self.fog_type,
+            FogType::Exponential
+        );
+        ui.same_line();
+        modified |= ui.radio_button(
+            "Exp2",
+            &mut self.fog_type,
+            FogType::ExponentialSquared
+        );
+
+        if self.fog_type == FogType::None {
+            if modified {
+                self.update_fog();
+            }
+            return;
+        }
+
+        if self.fog_type == FogType::Linear {
+            modified |= imgui::Drag::new("Start")
+                .speed(0.05)
+                .range(0.0, self.end)
+                .build(ui, &mut self.start);
+            modified |= imgui::Drag::new("End")
+                .speed(0.05)
+                .range(self.start, f32::INFINITY)
+                .build(ui, &mut self.end);
+        } else {
+            modified |= ui.slider("Density", 0.0, 2.0, &mut self.density);
+        }
+
+        modified |= ui.checkbox("Height fog", &mut self.is_height_fog);
+        if self.is_height_fog {
+            modified |= ui.slider("Height falloff", 0.0, 10.0, &mut self.height_falloff);
+            modified |= imgui::Drag::new("Height")
+                .speed(0.05)
+                .build(ui, &mut self.height);
+        }
+        ui.text_disabled("Fog blends to the background color");
+
+        if modified {
+            self.update_fog();
+        }
+    }
+}
@@ -1,20 +1,31 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::{utils::{color::Color, vector::Vector3}, rendering::{renderer::Renderer, lightning::{Lightning, UnlitLightning, DiffuseDirectLightning}}, ui::{ImguiEditor, drag_float3}};
+use crate::{utils::{color::Color, vector::Vector3}, rendering::{renderer::Renderer, light::{Light, LightType}, lightning::{Lightning, UnlitLightning, DiffuseDirectLightning, ToonLightning, ToonRamp, AmbientOcclusion, DEFAULT_TOON_BANDS_COUNT, DEFAULT_RIM_WIDTH, DEFAULT_SHADOW_BIAS, DEFAULT_AREA_LIGHT_SAMPLES_COUNT, DEFAULT_AO_RADIUS, DEFAULT_AO_SAMPLES_COUNT}}, ui::{ImguiEditor, drag_float3}};
 
 use super::Behaviour;
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 enum LightningType {
-    Unlit, Lit
+    Unlit, Lit, Toon
 }
 
 pub struct LightningBehaviour {
     renderer: Rc<RefCell<Renderer>>,
     lightning_type: LightningType,
-    light_direction: Vector3,
-    light_color: Color,
-    ambient_color: Color
+    lights: Vec<Light>,
+    ambient_color: Color,
+    cast_shadows: bool,
+    shadow_bias: f32,
+    area_light_samples_count: u32,
+    use_ambient_occlusion: bool,
+    ao_radius: f32,
+    ao_samples_count: u32,
+    show_ao_only: bool,
+    toon_bands_count: u32,
+    use_toon_ramp: bool,
+    toon_ramp_colors: Vec<Color>,
+    rim_color: Color,
+    rim_width: f32
 }
 
 impl LightningBehaviour {
@@ -22,9 +33,28 @@ impl LightningBehaviour {
         let mut behaviour = LightningBehaviour {
             renderer,
             lightning_type: LightningType::Lit,
-            light_direction: Vector3::new(1.0, -1.0, 0.5),
-            light_color: Color::new(255, 255, 255),
-            ambient_color: Color::new(45, 45, 45)
+            lights: vec![Light::new_directional(
+                &Vector3::new(1.0, -1.0, 0.5),
+                &Color::new(255, 255, 255),
+                1.0
+            )],
+            ambient_color: Color::new(45, 45, 45),
+            cast_shadows: true,
+            shadow_bias: DEFAULT_SHADOW_BIAS,
+            area_light_samples_count: DEFAULT_AREA_LIGHT_SAMPLES_COUNT,
+            use_ambient_occlusion: false,
+            ao_radius: DEFAULT_AO_RADIUS,
+            ao_samples_count: DEFAULT_AO_SAMPLES_COUNT,
+            show_ao_only: false,
+            toon_bands_count: DEFAULT_TOON_BANDS_COUNT,
+            use_toon_ramp: false,
+            toon_ramp_colors: vec![
+                Color::new(60, 50, 90),
+                Color::new(170, 150, 170),
+                Color::new(255, 245, 230)
+            ],
+            rim_color: Color::new(255, 255, 255),
+            rim_width: DEFAULT_RIM_WIDTH
         };
 
         behaviour.update_lightning();
@@ -34,16 +64,171 @@ impl LightningBehaviour {
 
     fn update_lightning(&mut self) {
         let lightning: Box<dyn Lightning> = match self.lightning_type {
-            LightningType::Unlit => Box::new(UnlitLightning::new(&self.ambient_color)),
-            LightningType::Lit => Box::new(DiffuseDirectLightning::new(
-                &self.light_direction,
-                &self.light_color,
-                &self.ambient_color)
-            )
+            LightningType::Unlit => {
+                let mut lightning = UnlitLightning::new(&self.ambient_color);
+                lightning.set_ambient_occlusion(self.build_ambient_occlusion());
+                Box::new(lightning)
+            },
+            LightningType::Lit => {
+                let mut lightning = DiffuseDirectLightning::new(
+                    self.lights.clone(),
+                    &self.ambient_color
+                );
+                lightning.set_cast_shadows(self.cast_shadows);
+                lightning.set_shadow_bias(self.shadow_bias);
+                lightning.set_area_light_samples_count(self.area_light_samples_count);
+                lightning.set_ambient_occlusion(self.build_ambient_occlusion());
+                Box::new(lightning)
+            },
+            LightningType::Toon => {
+                let mut lightning = ToonLightning::new(
+                    self.lights.clone(),
+                    &self.ambient_color
+                );
+                lightning.set_ramp(if self.use_toon_ramp {
+                    ToonRamp::Colors(self.toon_ramp_colors.clone())
+                } else {
+                    ToonRamp::Bands(self.toon_bands_count)
+                });
+                lightning.set_rim_color(&self.rim_color);
+                lightning.set_rim_width(self.rim_width);
+                lightning.set_cast_shadows(self.cast_shadows);
+                lightning.set_shadow_bias(self.shadow_bias);
+                Box::new(lightning)
+            }
         };
 
         self.renderer.borrow_mut().set_lightning(lightning);
     }
+
+    fn build_ambient_occlusion(&self) -> Option<AmbientOcclusion> {
+        if !self.use_ambient_occlusion {
+            return None;
+        }
+
+        let mut ao = AmbientOcclusion::new(self.ao_radius, self.ao_samples_count);
+        ao.is_debug_view = self.show_ao_only;
+        return Some(ao);
+    }
+
+    fn draw_lights_ui(&mut self, ui: &imgui::Ui) -> bool {
+        let mut modified = false;
+        let mut removed_index = None;
+
+        for (i, light) in self.lights.iter_mut().enumerate() {
+            let _id = ui.push_id_usize(i);
+
+            if let Some(_node) = ui.tree_node(format!("Light {}", i + 1)) {
+                modified |= LightningBehaviour::draw_light_ui(ui, light);
+
+                if ui.button("Remove light") {
+                    removed_index = Some(i);
+                }
+            }
+        }
+
+        if let Some(i) = removed_index {
+            self.lights.remove(i);
+            modified = true;
+        }
+
+        if ui.button("Add light") {
+            self.lights.push(Light::new_point(
+                &Vector3::new(0.0, 1.5, -1.0),
+                &Color::new(255, 255, 255),
+                2.0
+            ));
+            modified = true;
+        }
+
+        return modified;
+    }
+
+    fn draw_light_ui(ui: &imgui::Ui, light: &mut Light) -> bool {
+        let mut modified = false;
+
+        modified |= ui.radio_button("Directional", &mut light.light_type, LightType::Directional);
+        ui.same_line();
+        modified |= ui.radio_button("Point", &mut light.light_type, LightType::Point);
+        ui.same_line();
+        modified |= ui.radio_button("Spot", &mut light.light_type, LightType::Spot);
+        ui.same_line();
+        modified |= ui.radio_button("Rect", &mut light.light_type, LightType::Rect);
+        ui.same_line();
+        modified |= ui.radio_button("Sphere", &mut light.light_type, LightType::Sphere);
+
+        modified |= ui.color_edit4("Color", &mut light.color);
+        modified |= imgui::Drag::new("Intensity")
+            .speed(0.02)
+            .range(0.0, f32::INFINITY)
+            .build(ui, &mut light.intensity);
+
+        if light.light_type != LightType::Directional {
+            modified |= drag_float3(ui, "Position", &mut light.position);
+        }
+        if light.light_type != LightType::Point && light.light_type != LightType::Sphere {
+            modified |= drag_float3(ui, "Direction", &mut light.direction);
+        }
+        if light.light_type == LightType::Spot {
+            modified |= ui.slider("Inner angle", 0.0, 90.0, &mut light.inner_angle);
+            modified |= ui.slider("Outer angle", 0.0, 90.0, &mut light.outer_angle);
+            light.inner_angle = light.inner_angle.min(light.outer_angle);
+        }
+        if light.light_type == LightType::Rect {
+            modified |= imgui::Drag::new("Width")
+                .speed(0.01)
+                .range(0.0, f32::INFINITY)
+                .build(ui, &mut light.width);
+            modified |= imgui::Drag::new("Height")
+                .speed(0.01)
+                .range(0.0, f32::INFINITY)
+                .build(ui, &mut light.height);
+        }
+        if light.light_type == LightType::Sphere {
+            modified |= imgui::Drag::new("Radius")
+                .speed(0.01)
+                .range(0.0, f32::INFINITY)
+                .build(ui, &mut light.radius);
+        }
+
+        return modified;
+    }
+
+    fn draw_toon_ui(&mut self, ui: &imgui::Ui) -> bool {
+        let mut modified = false;
+
+        modified |= ui.checkbox("Color ramp", &mut self.use_toon_ramp);
+        if self.use_toon_ramp {
+            let mut removed_index = None;
+
+            for (i, color) in self.toon_ramp_colors.iter_mut().enumerate() {
+                let _id = ui.push_id_usize(i);
+
+                modified |= ui.color_edit4(format!("Band {}", i + 1), color);
+                ui.same_line();
+                if ui.button("Remove") {
+                    removed_index = Some(i);
+                }
+            }
+
+            if let Some(i) = removed_index {
+                self.toon_ramp_colors.remove(i);
+                modified = true;
+            }
+
+            if ui.button("Add band") {
+                self.toon_ramp_colors.push(Color::new(255, 255, 255));
+                modified = true;
+            }
+        } else {
+            modified |= ui.slider("Bands", 1, 8, &mut self.toon_bands_count);
+        }
+
+        modified |= ui.color_edit4("Rim color", &mut self.rim_color);
+        modified |= ui.slider("Rim width", 0.0, 1.0, &mut self.rim_width);
+
+        return modified;
+    }
 }
 
 impl Behaviour for LightningBehaviour {
@@ -66,18 +251,50 @@ impl ImguiEditor for LightningBehaviour {
         &mut self.lightning_type,
             LightningType::Lit
         );
+        ui.same_line();
+        modified |= ui.radio_button(
+            "Toon",
+            &mut self.lightning_type,
+            LightningType::Toon
+        );
         
         match self.lightning_type {
             LightningType::Unlit => {
                 modified |= ui.color_edit4("Ambient color", &mut self.ambient_color);
             },
             LightningType::Lit => {
-                modified |= drag_float3(ui, "Light direction", &mut self.light_direction);
-                modified |= ui.color_edit4("Light color", &mut self.light_color);
                 modified |= ui.color_edit4("Ambient color", &mut self.ambient_color);
+                modified |= self.draw_lights_ui(ui);
+                modified |= ui.checkbox("Cast shadows", &mut self.cast_shadows);
+                ui.disabled(!self.cast_shadows, || {
+                    modified |= imgui::Drag::new("Shadow bias")
+                        .speed(0.0005)
+                        .range(0.0, 1.0)
+                        .build(ui, &mut self.shadow_bias);
+                });
+                modified |= ui.slider("Area light samples", 1, 64, &mut self.area_light_samples_count);
+            },
+            LightningType::Toon => {
+                modified |= ui.color_edit4("Ambient color", &mut self.ambient_color);
+                modified |= self.draw_lights_ui(ui);
+                modified |= self.draw_toon_ui(ui);
+                modified |= ui.checkbox("Cast shadows", &mut self.cast_shadows);
             }
         };
 
+        // Flat toon bands are not shaded by ambient occlusion
+        if self.lightning_type != LightningType::Toon {
+            modified |= ui.checkbox("Ambient occlusion", &mut self.use_ambient_occlusion);
+            ui.disabled(!self.use_ambient_occlusion, || {
+                modified |= imgui::Drag::new("AO radius")
+                    .speed(0.01)
+                    .range(0.0, f32::INFINITY)
+                    .build(ui, &mut self.ao_radius);
+                modified |= ui.slider("AO samples", 1, 64, &mut self.ao_samples_count);
+                modified |= ui.checkbox("Show AO only", &mut self.show_ao_only);
+            });
+        }
+
         if modified {
             self.update_lightning();
         }
@@ -1,20 +1,71 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::{utils::color::Color, rendering::{renderer::Renderer, material::{FlatMaterial, CheckerMaterial, Material}}, ui::{ImguiEditor, drag_float}};
+use crate::{raycaster::BoxFace, utils::{color::{Color, ColorRamp}, noise::PerlinNoise}, image::load_image, rendering::{renderer::Renderer, bump::{BumpMaterial, BumpPattern}, material_graph::GraphMaterial, material::{FlatMaterial, CheckerMaterial, CutoutMaterial, FaceMaterial, NoiseMaterial, NoisePattern, DielectricMaterial, PbrMaterial, EmissiveMaterial, TextureMaterial, TextureMapping, MatcapMaterial, Material, Reflectance}, texture::{Texture, TextureFilter, TextureWrap}}, ui::{ImguiEditor, drag_float, edit_color_ramp_stops}};
 
-use super::Behaviour;
+use super::{Behaviour, material_graph_editor::MaterialGraphEditor};
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 enum MaterialType {
-    Flat, Checker
+    Flat, Checker, Texture, Noise, Dielectric, Pbr, Emissive, PerFace, Graph, Matcap
 }
 
+/// Relief added on top of any material type.
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum BumpType {
+    None, NormalMap, Bricks, Dimples, Noise
+}
+
+const GLASS_REFRACTION_INDEX: f32 = 1.5;
+const WATER_REFRACTION_INDEX: f32 = 1.33;
+
+const CLAY_MATCAP_SIZE: usize = 256;
+
+/// Colors of an orientation cube, faces along X are red, along Y green and along Z blue.
+/// Faces on the negative side are darker.
+const ORIENTATION_FACE_COLORS: [Color; 6] = [
+    Color { r: 220, g: 50, b: 50, a: 255 }, Color { r: 110, g: 25, b: 25, a: 255 },
+    Color { r: 50, g: 200, b: 50, a: 255 }, Color { r: 25, g: 100, b: 25, a: 255 },
+    Color { r: 50, g: 90, b: 230, a: 255 }, Color { r: 25, g: 45, b: 115, a: 255 }
+];
+
 pub struct MaterialBehaviour {
     renderer: Rc<RefCell<Renderer>>,
     material_type: MaterialType,
     first_color: Color,
     second_color: Color,
-    scale: f32
+    scale: f32,
+    reflectivity: f32,
+    refraction_index: f32,
+    is_specular: bool,
+    specular_color: Color,
+    shininess: f32,
+    metallic: f32,
+    roughness: f32,
+    emission_intensity: f32,
+    texture_path: String,
+    texture: Option<Texture>,
+    texture_error: Option<String>,
+    texture_mapping: TextureMapping,
+    texture_filter: TextureFilter,
+    texture_wrap: TextureWrap,
+    noise_pattern: NoisePattern,
+    noise_octaves_count: u32,
+    noise_distortion: f32,
+    noise_seed: u32,
+    ramp_stops: Vec<(f32, Color)>,
+    face_colors: [Color; 6],
+    graph_editor: MaterialGraphEditor,
+    matcap_path: String,
+    matcap: Texture,
+    matcap_error: Option<String>,
+    is_cutout: bool,
+    alpha_threshold: f32,
+    bump_type: BumpType,
+    bump_scale: f32,
+    bump_strength: f32,
+    normal_map_path: String,
+    normal_map: Option<Texture>,
+    normal_map_error: Option<String>
 }
 
 impl MaterialBehaviour {
@@ -24,7 +75,39 @@ impl MaterialBehaviour {
             material_type: MaterialType::Checker,
             first_color: Color::new(255, 255, 255),
             second_color: Color::new(127, 127, 127),
-            scale: 1.0
+            scale: 1.0,
+            reflectivity: 0.0,
+            refraction_index: GLASS_REFRACTION_INDEX,
+            is_specular: true,
+            specular_color: Color::new(127, 127, 127),
+            shininess: 32.0,
+            metallic: 0.0,
+            roughness: 0.5,
+            emission_intensity: 2.0,
+            texture_path: String::new(),
+            texture: None,
+            texture_error: None,
+            texture_mapping: TextureMapping::Uv,
+            texture_filter: TextureFilter::Trilinear,
+            texture_wrap: TextureWrap::Repeat,
+            noise_pattern: NoisePattern::Marble,
+            noise_octaves_count: 4,
+            noise_distortion: 2.0,
+            noise_seed: 1,
+            ramp_stops: vec![(0.0, Color::new(240, 240, 235)), (0.6, Color::new(200, 200, 205)), (1.0, Color::new(60, 60, 70))],
+            face_colors: ORIENTATION_FACE_COLORS,
+            graph_editor: MaterialGraphEditor::new(),
+            matcap_path: String::new(),
+            matcap: MaterialBehaviour::create_clay_matcap(),
+            matcap_error: None,
+            is_cutout: false,
+            alpha_threshold: 0.5,
+            bump_type: BumpType::None,
+            bump_scale: 1.0,
+            bump_strength: 1.0,
+            normal_map_path: String::new(),
+            normal_map: None,
+            normal_map_error: None
         };
 
         behaviour.update_material();
@@ -34,17 +117,331 @@ impl MaterialBehaviour {
 
     fn update_material(&mut self) {
         let material: Box<dyn Material> = match self.material_type {
-            MaterialType::Flat => Box::new(FlatMaterial { color: self.first_color }),
+            MaterialType::Flat => Box::new(
+                FlatMaterial {
+                    color: self.first_color,
+                    reflectivity: self.reflectivity,
+                    reflectance: self.get_reflectance()
+                }
+            ),
             MaterialType::Checker => Box::new(
                 CheckerMaterial {
                     scale: self.scale,
                     first_color: self.first_color,
-                    second_color: self.second_color
+                    second_color: self.second_color,
+                    reflectivity: self.reflectivity,
+                    reflectance: self.get_reflectance()
+                }
+            ),
+            MaterialType::Noise => Box::new(
+                NoiseMaterial {
+                    pattern: self.noise_pattern,
+                    noise: PerlinNoise::new(self.noise_seed),
+                    ramp: ColorRamp::new(self.ramp_stops.clone()),
+                    scale: self.scale,
+                    octaves_count: self.noise_octaves_count,
+                    distortion: self.noise_distortion,
+                    reflectivity: self.reflectivity,
+                    reflectance: self.get_reflectance()
+                }
+            ),
+            MaterialType::Texture => match &self.texture {
+                Some(texture) => Box::new(
+                    TextureMaterial {
+                        texture: texture.with_sampling(self.texture_filter, self.texture_wrap),
+                        mapping: self.texture_mapping,
+                        scale: self.scale,
+                        reflectivity: self.reflectivity,
+                        reflectance: self.get_reflectance()
+                    }
+                ),
+                // Show the first color until some texture is loaded
+                None => Box::new(
+                    FlatMaterial {
+                        color: self.first_color,
+                        reflectivity: self.reflectivity,
+                        reflectance: self.get_reflectance()
+                    }
+                )
+            },
+            MaterialType::Dielectric => Box::new(
+                DielectricMaterial {
+                    color: self.first_color,
+                    refraction_index: self.refraction_index
+                }
+            ),
+            MaterialType::Pbr => Box::new(
+                PbrMaterial {
+                    base_color: self.first_color,
+                    metallic: self.metallic,
+                    roughness: self.roughness
+                }
+            ),
+            MaterialType::Emissive => Box::new(
+                EmissiveMaterial {
+                    color: self.first_color,
+                    intensity: self.emission_intensity
                 }
+            ),
+            MaterialType::PerFace => Box::new(
+                FaceMaterial {
+                    faces: self.face_colors.map(|color| -> Box<dyn Material> {
+                        Box::new(FlatMaterial {
+                            color,
+                            reflectivity: self.reflectivity,
+                            reflectance: self.get_reflectance()
+                        })
+                    })
+                }
+            ),
+            MaterialType::Graph => Box::new(
+                GraphMaterial::new(self.graph_editor.get_graph(), self.reflectivity, self.get_reflectance())
+            ),
+            MaterialType::Matcap => Box::new(
+                MatcapMaterial { texture: self.matcap.clone() }
             )
         };
+        let material: Box<dyn Material> = if self.is_cutout {
+            Box::new(CutoutMaterial { material, threshold: self.alpha_threshold })
+        } else {
+            material
+        };
+        let material = self.apply_bumps(material);
         self.renderer.borrow_mut().set_material(material);
     }
+
+    fn apply_bumps(&self, material: Box<dyn Material>) -> Box<dyn Material> {
+        let pattern = match self.bump_type {
+            BumpType::None => return material,
+            BumpType::NormalMap => match &self.normal_map {
+                Some(texture) => BumpPattern::NormalMap(texture.clone()),
+                None => return material
+            },
+            BumpType::Bricks => BumpPattern::Bricks,
+            BumpType::Dimples => BumpPattern::Dimples(PerlinNoise::new(self.noise_seed)),
+            BumpType::Noise => BumpPattern::Noise(PerlinNoise::new(self.noise_seed))
+        };
+
+        Box::new(BumpMaterial {
+            material,
+            pattern,
+            scale: self.bump_scale,
+            strength: self.bump_strength
+        })
+    }
+
+    fn load_texture(&mut self) {
+        match load_image(&self.texture_path) {
+            Ok(image) => {
+                self.texture = Some(Texture::new(image));
+                self.texture_error = None;
+            },
+            Err(err) => {
+                self.texture = None;
+                self.texture_error = Some(err);
+            }
+        }
+    }
+
+    /// Create the default matcap. Matcaps are looked up within the sphere disc only,
+    /// so they are clamped instead of repeated.
+    fn create_clay_matcap() -> Texture {
+        Texture::new(MatcapMaterial::create_clay_image(CLAY_MATCAP_SIZE))
+            .with_sampling(TextureFilter::Bilinear, TextureWrap::Clamp)
+    }
+
+    fn load_matcap(&mut self) {
+        match load_image(&self.matcap_path) {
+            Ok(image) => {
+                self.matcap = Texture::new(image).with_sampling(TextureFilter::Bilinear, TextureWrap::Clamp);
+                self.matcap_error = None;
+            },
+            Err(err) => self.matcap_error = Some(err)
+        }
+    }
+
+    fn draw_matcap_ui(&mut self, ui: &imgui::Ui) -> bool {
+        ui.input_text("Matcap path", &mut self.matcap_path)
+            .hint("path/to/matcap.png")
+            .build();
+
+        let mut modified = ui.button("Load matcap");
+        if modified {
+            self.load_matcap();
+        }
+        ui.same_line();
+        if ui.button("Clay") {
+            self.matcap = MaterialBehaviour::create_clay_matcap();
+            self.matcap_error = None;
+            modified = true;
+        }
+
+        if let Some(err) = &self.matcap_error {
+            ui.text_colored([1.0, 0.3, 0.3, 1.0], err);
+        }
+
+        modified
+    }
+
+    fn draw_texture_ui(&mut self, ui: &imgui::Ui) -> bool {
+        ui.input_text("Texture path", &mut self.texture_path)
+            .hint("path/to/texture.png")
+            .build();
+
+        let mut modified = ui.button("Load texture");
+        if modified {
+            self.load_texture();
+        }
+
+        if let Some(err) = &self.texture_error {
+            ui.text_colored([1.0, 0.3, 0.3, 1.0], err);
+        }
+
+        ui.text("Mapping");
+        modified |= ui.radio_button("UV", &mut self.texture_mapping, TextureMapping::Uv);
+        ui.same_line();
+        modified |= ui.radio_button("Triplanar", &mut self.texture_mapping, TextureMapping::Triplanar);
+
+        ui.text("Filter");
+        modified |= ui.radio_button("Nearest", &mut self.texture_filter, TextureFilter::Nearest);
+        ui.same_line();
+        modified |= ui.radio_button("Bilinear", &mut self.texture_filter, TextureFilter::Bilinear);
+        ui.same_line();
+        modified |= ui.radio_button("Trilinear", &mut self.texture_filter, TextureFilter::Trilinear);
+
+        ui.text("Wrap");
+        modified |= ui.radio_button("Repeat", &mut self.texture_wrap, TextureWrap::Repeat);
+        ui.same_line();
+        modified |= ui.radio_button("Clamp", &mut self.texture_wrap, TextureWrap::Clamp);
+        ui.same_line();
+        modified |= ui.radio_button("Mirror", &mut self.texture_wrap, TextureWrap::Mirror);
+
+        modified |= drag_float(ui, "Scale", &mut self.scale);
+
+        modified
+    }
+
+    fn load_normal_map(&mut self) {
+        match load_image(&self.normal_map_path) {
+            Ok(image) => {
+                self.normal_map = Some(Texture::new(image));
+                self.normal_map_error = None;
+            },
+            Err(err) => {
+                self.normal_map = None;
+                self.normal_map_error = Some(err);
+            }
+        }
+    }
+
+    fn draw_bump_ui(&mut self, ui: &imgui::Ui) -> bool {
+        let mut modified = false;
+
+        ui.text("Relief");
+        modified |= ui.radio_button("None##bump", &mut self.bump_type, BumpType::None);
+        ui.same_line();
+        modified |= ui.radio_button("Normal map", &mut self.bump_type, BumpType::NormalMap);
+        ui.same_line();
+        modified |= ui.radio_button("Bricks", &mut self.bump_type, BumpType::Bricks);
+        modified |= ui.radio_button("Dimples", &mut self.bump_type, BumpType::Dimples);
+        ui.same_line();
+        modified |= ui.radio_button("Noise##bump", &mut self.bump_type, BumpType::Noise);
+
+        if self.bump_type == BumpType::None {
+            return modified;
+        }
+
+        if self.bump_type == BumpType::NormalMap {
+            ui.input_text("Normal map path", &mut self.normal_map_path)
+                .hint("path/to/normal.png")
+                .build();
+
+            if ui.button("Load normal map") {
+                self.load_normal_map();
+                modified = true;
+            }
+
+            if let Some(err) = &self.normal_map_error {
+                ui.text_colored([1.0, 0.3, 0.3, 1.0], err);
+            }
+        }
+
+        modified |= drag_float(ui, "Relief scale", &mut self.bump_scale);
+        modified |= ui.slider("Relief strength", 0.0, 4.0, &mut self.bump_strength);
+
+        modified
+    }
+
+    fn draw_noise_ui(&mut self, ui: &imgui::Ui) -> bool {
+        let mut modified = false;
+
+        ui.text("Pattern");
+        modified |= ui.radio_button("Perlin", &mut self.noise_pattern, NoisePattern::Perlin);
+        ui.same_line();
+        modified |= ui.radio_button("fBm", &mut self.noise_pattern, NoisePattern::Fbm);
+        ui.same_line();
+        modified |= ui.radio_button("Turbulence", &mut self.noise_pattern, NoisePattern::Turbulence);
+        modified |= ui.radio_button("Marble", &mut self.noise_pattern, NoisePattern::Marble);
+        ui.same_line();
+        modified |= ui.radio_button("Wood", &mut self.noise_pattern, NoisePattern::Wood);
+
+        modified |= drag_float(ui, "Scale", &mut self.scale);
+        ui.disabled(self.noise_pattern == NoisePattern::Perlin, || {
+            modified |= ui.slider("Octaves", 1, 8, &mut self.noise_octaves_count);
+        });
+        if matches!(self.noise_pattern, NoisePattern::Marble | NoisePattern::Wood) {
+            modified |= ui.slider("Distortion", 0.0, 10.0, &mut self.noise_distortion);
+        }
+        modified |= imgui::Drag::new("Seed").build(ui, &mut self.noise_seed);
+
+        ui.text("Color ramp");
+        modified |= edit_color_ramp_stops(ui, &mut self.ramp_stops);
+
+        modified
+    }
+
+    fn draw_faces_ui(&mut self, ui: &imgui::Ui) -> bool {
+        let mut modified = false;
+
+        for (face, color) in BoxFace::ALL.iter().zip(self.face_colors.iter_mut()) {
+            modified |= ui.color_edit4(format!("{} face", face.get_name()), color);
+        }
+
+        if ui.button("Orientation colors") {
+            self.face_colors = ORIENTATION_FACE_COLORS;
+            modified = true;
+        }
+
+        modified
+    }
+
+    /// Draw the node editor of the material graph in its own window, while the graph material is used.
+    pub fn draw_graph_window(&mut self, ui: &imgui::Ui) {
+        if self.material_type != MaterialType::Graph {
+            return;
+        }
+
+        let mut modified = false;
+        ui.window("Material graph")
+            .size([560.0, 460.0], imgui::Condition::FirstUseEver)
+            .position([300.0, 20.0], imgui::Condition::FirstUseEver)
+            .build(|| modified = self.graph_editor.draw_ui(ui));
+
+        if modified {
+            self.update_material();
+        }
+    }
+
+    fn get_reflectance(&self) -> Reflectance {
+        if self.is_specular {
+            Reflectance::BlinnPhong {
+                specular_color: self.specular_color,
+                shininess: self.shininess
+            }
+        } else {
+            Reflectance::Diffuse
+        }
+    }
 }
 
 impl Behaviour for MaterialBehaviour {
@@ -67,6 +464,52 @@ impl ImguiEditor for MaterialBehaviour {
             &mut self.material_type,
             MaterialType::Checker
         );
+        ui.same_line();
+        modified |= ui.radio_button(
+            "Texture",
+            &mut self.material_type,
+            MaterialType::Texture
+        );
+        ui.same_line();
+        modified |= ui.radio_button(
+            "Noise",
+            &mut self.material_type,
+            MaterialType::Noise
+        );
+        modified |= ui.radio_button(
+            "Dielectric",
+            &mut self.material_type,
+            MaterialType::Dielectric
+        );
+        ui.same_line();
+        modified |= ui.radio_button(
+            "PBR",
+            &mut self.material_type,
+            MaterialType::Pbr
+        );
+        ui.same_line();
+        modified |= ui.radio_button(
+            "Emissive",
+            &mut self.material_type,
+            MaterialType::Emissive
+        );
+        ui.same_line();
+        modified |= ui.radio_button(
+            "Per face",
+            &mut self.material_type,
+            MaterialType::PerFace
+        );
+        modified |= ui.radio_button(
+            "Node graph",
+            &mut self.material_type,
+            MaterialType::Graph
+        );
+        ui.same_line();
+        modified |= ui.radio_button(
+            "Matcap",
+            &mut self.material_type,
+            MaterialType::Matcap
+        );
 
         match self.material_type {
             MaterialType::Flat => {
@@ -76,9 +519,80 @@ impl ImguiEditor for MaterialBehaviour {
                 modified |= ui.color_edit4("First color", &mut self.first_color);
                 modified |= ui.color_edit4("Second color", &mut self.second_color);
                 modified |= drag_float(ui, "Scale", &mut self.scale);
+            },
+            MaterialType::Texture => {
+                modified |= self.draw_texture_ui(ui);
+            },
+            MaterialType::Noise => {
+                modified |= self.draw_noise_ui(ui);
+            },
+            MaterialType::Dielectric => {
+                modified |= ui.color_edit4("Tint color", &mut self.first_color);
+                modified |= imgui::Drag::new("Refraction index")
+                    .speed(0.005)
+                    .range(1.0, 3.0)
+                    .build(ui, &mut self.refraction_index);
+
+                if ui.button("Glass") {
+                    self.refraction_index = GLASS_REFRACTION_INDEX;
+                    modified = true;
+                }
+                ui.same_line();
+                if ui.button("Water") {
+                    self.refraction_index = WATER_REFRACTION_INDEX;
+                    modified = true;
+                }
+            },
+            MaterialType::Pbr => {
+                modified |= ui.color_edit4("Base color", &mut self.first_color);
+                modified |= ui.slider("Metallic", 0.0, 1.0, &mut self.metallic);
+                modified |= ui.slider("Roughness", 0.0, 1.0, &mut self.roughness);
+            },
+            MaterialType::Emissive => {
+                modified |= ui.color_edit4("Emission color", &mut self.first_color);
+                modified |= imgui::Drag::new("Intensity")
+                    .speed(0.02)
+                    .range(0.0, f32::INFINITY)
+                    .build(ui, &mut self.emission_intensity);
+            },
+            MaterialType::PerFace => {
+                modified |= self.draw_faces_ui(ui);
+            },
+            MaterialType::Graph => {
+                ui.text_wrapped("Nodes are edited in the material graph window");
+            },
+            MaterialType::Matcap => {
+                modified |= self.draw_matcap_ui(ui);
             }
         };
 
+        // Dielectric and PBR materials derive their reflections from the Fresnel term,
+        // emissive and matcap ones have no reflections
+        if matches!(
+            self.material_type,
+            MaterialType::Flat | MaterialType::Checker | MaterialType::Texture | MaterialType::Noise | MaterialType::PerFace
+                | MaterialType::Graph
+        ) {
+            modified |= ui.slider("Reflectivity", 0.0, 1.0, &mut self.reflectivity);
+
+            modified |= ui.checkbox("Specular", &mut self.is_specular);
+            ui.disabled(!self.is_specular, || {
+                modified |= ui.color_edit4("Specular color", &mut self.specular_color);
+                modified |= ui.slider_config("Shininess", 1.0, 512.0)
+                    .flags(imgui::SliderFlags::LOGARITHMIC)
+                    .build(&mut self.shininess);
+            });
+        }
+
+        // Colors with alpha below 255 are blended with what is behind, unless cut out
+        modified |= ui.checkbox("Alpha cutout", &mut self.is_cutout);
+        if self.is_cutout {
+            modified |= ui.slider("Alpha threshold", 0.0, 1.0, &mut self.alpha_threshold);
+        }
+
+        ui.separator();
+        modified |= self.draw_bump_ui(ui);
+
         if modified {
             self.update_material();
         }
@@ -0,0 +1,417 @@
+use imgui::MouseButton;
+
+use crate::{
+    image::load_image,
+    rendering::{material_graph::{MaterialGraph, GraphNode, NodeKind, MathOperation, SocketType, OUTPUT_NODE_INDEX}, texture::Texture},
+    utils::{color::{Color, ColorRamp}, vector::Vector3},
+    ui::{drag_float, edit_color_ramp_stops}
+};
+
+const NODE_WIDTH: f32 = 130.0;
+const HEADER_HEIGHT: f32 = 22.0;
+const ROW_HEIGHT: f32 = 20.0;
+const SOCKET_RADIUS: f32 = 5.0;
+/// Horizontal distance of the control points of link curves from their ends.
+const LINK_CURVATURE: f32 = 50.0;
+const GRID_STEP: f32 = 32.0;
+/// Height of the panel with properties of the selected node, below the canvas.
+const PROPERTIES_HEIGHT: f32 = 190.0;
+
+const CANVAS_COLOR: [f32; 4] = [0.12, 0.12, 0.14, 1.0];
+const GRID_COLOR: [f32; 4] = [0.2, 0.2, 0.22, 1.0];
+const NODE_COLOR: [f32; 4] = [0.22, 0.22, 0.25, 1.0];
+const HEADER_COLOR: [f32; 4] = [0.3, 0.35, 0.5, 1.0];
+const OUTPUT_HEADER_COLOR: [f32; 4] = [0.5, 0.3, 0.3, 1.0];
+const BORDER_COLOR: [f32; 4] = [0.4, 0.4, 0.45, 1.0];
+const SELECTED_BORDER_COLOR: [f32; 4] = [1.0, 0.8, 0.3, 1.0];
+const SOCKET_COLOR: [f32; 4] = [0.8, 0.8, 0.5, 1.0];
+const LINK_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+const TEXT_COLOR: [f32; 4] = [0.9, 0.9, 0.9, 1.0];
+
+/// What dragging with the left mouse button over the canvas does.
+#[derive(Clone, Copy)]
+enum Interaction {
+    None,
+    Panning,
+    MovingNode(usize),
+    /// Dragging a new link from the output of the node.
+    Linking(usize)
+}
+
+/// Node editor of a material graph. Nodes are dragged by their body, links are dragged from
+/// outputs onto inputs or off inputs to remove them, the canvas is panned by dragging its empty
+/// space and right click opens the menu adding nodes.
+pub struct MaterialGraphEditor {
+    graph: MaterialGraph,
+    /// Offset of the canvas content, changed by panning.
+    scrolling: [f32; 2],
+    selected_node: Option<usize>,
+    interaction: Interaction,
+    /// Canvas position of the node added from the context menu.
+    new_node_position: [f32; 2],
+    error: Option<String>
+}
+
+impl MaterialGraphEditor {
+    /// Create editor of the graph coloring the surface by noise through a color ramp.
+    pub fn new() -> MaterialGraphEditor {
+        let mut graph = MaterialGraph::new([340.0, 30.0]);
+        let noise = graph.add_node(NodeKind::Noise { seed: 1, octaves_count: 4 }, [20.0, 30.0]);
+        let ramp = graph.add_node(
+            NodeKind::ColorRamp(ColorRamp::new(vec![(0.3, Color::new(40, 60, 120)), (0.7, Color::new(240, 200, 120))])),
+            [180.0, 30.0]
+        );
+        graph.link(noise, ramp, 0).expect("Default graph links must be valid");
+        graph.link(ramp, OUTPUT_NODE_INDEX, 0).expect("Default graph links must be valid");
+
+        MaterialGraphEditor {
+            graph,
+            scrolling: [0.0, 0.0],
+            selected_node: None,
+            interaction: Interaction::None,
+            new_node_position: [0.0, 0.0],
+            error: None
+        }
+    }
+
+    pub fn get_graph(&self) -> &MaterialGraph {
+        &self.graph
+    }
+
+    /// Draw the canvas and properties of the selected node.
+    /// Returns whether the graph changed in a way affecting its material.
+    pub fn draw_ui(&mut self, ui: &imgui::Ui) -> bool {
+        let mut modified = false;
+
+        let canvas_height = (ui.content_region_avail()[1] - PROPERTIES_HEIGHT).max(ROW_HEIGHT);
+        ui.child_window("Canvas")
+            .size([0.0, canvas_height])
+            .border(true)
+            .scroll_bar(false)
+            .scrollable(false)
+            .movable(false)
+            .build(|| modified |= self.draw_canvas(ui));
+
+        if let Some(err) = &self.error {
+            ui.text_colored([1.0, 0.3, 0.3, 1.0], err);
+        }
+        modified |= self.draw_properties(ui);
+
+        modified
+    }
+
+    fn draw_canvas(&mut self, ui: &imgui::Ui) -> bool {
+        let mut modified = false;
+
+        let origin = ui.cursor_screen_pos();
+        let size = ui.content_region_avail();
+        let offset = [origin[0] + self.scrolling[0], origin[1] + self.scrolling[1]];
+        let mouse = ui.io().mouse_pos;
+
+        // Button over the whole canvas keeps dragging from moving the window
+        ui.invisible_button("Canvas", [size[0].max(1.0), size[1].max(1.0)]);
+        let is_hovered = ui.is_item_hovered();
+
+        if is_hovered && ui.is_mouse_clicked(MouseButton::Left) {
+            modified |= self.start_interaction(mouse, offset);
+        }
+
+        if ui.is_mouse_down(MouseButton::Left) {
+            let delta = ui.io().mouse_delta;
+            match self.interaction {
+                Interaction::Panning => {
+                    self.scrolling = [self.scrolling[0] + delta[0], self.scrolling[1] + delta[1]];
+                },
+                Interaction::MovingNode(index) => {
+                    if let Some(node) = self.graph.get_mut_node(index) {
+                        node.position = [node.position[0] + delta[0], node.position[1] + delta[1]];
+                    }
+                },
+                _ => ()
+            }
+        } else {
+            if let Interaction::Linking(from) = self.interaction {
+                if let Some((to, input)) = self.find_input_socket(mouse, offset) {
+                    match self.graph.link(from, to, input) {
+                        Ok(()) => {
+                            self.error = None;
+                            modified = true;
+                        },
+                        Err(err) => self.error = Some(err)
+                    }
+                }
+            }
+            self.interaction = Interaction::None;
+        }
+
+        if is_hovered && ui.is_mouse_clicked(MouseButton::Right) {
+            self.new_node_position = [mouse[0] - offset[0], mouse[1] - offset[1]];
+            ui.open_popup("Add node");
+        }
+        ui.popup("Add node", || modified |= self.draw_add_node_menu(ui));
+
+        self.draw_graph(ui, origin, size, offset, mouse);
+
+        modified
+    }
+
+    /// Pick what the left click starts to drag. Returns whether a link was removed.
+    fn start_interaction(&mut self, mouse: [f32; 2], offset: [f32; 2]) -> bool {
+        // Later nodes are drawn on top, so they are picked first
+        for (index, node) in self.graph.get_nodes().iter().enumerate().rev() {
+            let min = to_screen(node.position, offset);
+
+            if node.kind.has_output() && is_near(mouse, get_output_socket(min)) {
+                self.interaction = Interaction::Linking(index);
+                return false;
+            }
+
+            let linked_input = node.inputs.iter().enumerate().find_map(|(i, input)| {
+                input.link.filter(|_| is_near(mouse, get_input_socket(min, i))).map(|from| (i, from))
+            });
+            if let Some((i, from)) = linked_input {
+                // Dragging the link off the input detaches it
+                self.graph.unlink(index, i);
+                self.interaction = Interaction::Linking(from);
+                return true;
+            }
+
+            let size = get_node_size(node);
+            if mouse[0] >= min[0] && mouse[0] <= min[0] + size[0] && mouse[1] >= min[1] && mouse[1] <= min[1] + size[1] {
+                self.selected_node = Some(index);
+                self.interaction = Interaction::MovingNode(index);
+                return false;
+            }
+        }
+
+        self.selected_node = None;
+        self.interaction = Interaction::Panning;
+        return false;
+    }
+
+    fn find_input_socket(&self, mouse: [f32; 2], offset: [f32; 2]) -> Option<(usize, usize)> {
+        self.graph.get_nodes().iter().enumerate().rev().find_map(|(index, node)| {
+            let min = to_screen(node.position, offset);
+            (0..node.inputs.len())
+                .find(|&i| is_near(mouse, get_input_socket(min, i)))
+                .map(|i| (index, i))
+        })
+    }
+
+    fn draw_add_node_menu(&mut self, ui: &imgui::Ui) -> bool {
+        let kinds = [
+            ("Position", NodeKind::Position),
+            ("Normal", NodeKind::Normal),
+            ("UV", NodeKind::Uv),
+            ("Noise", NodeKind::Noise { seed: 1, octaves_count: 4 }),
+            ("Checker", NodeKind::Checker),
+            ("Mix", NodeKind::Mix),
+            ("Math", NodeKind::Math(MathOperation::Multiply)),
+            ("Color ramp", NodeKind::ColorRamp(ColorRamp::new(vec![(0.0, Color::new(0, 0, 0)), (1.0, Color::new(255, 255, 255))]))),
+            ("Texture", NodeKind::Texture { path: String::new(), texture: None })
+        ];
+
+        for (label, kind) in kinds {
+            if ui.menu_item(label) {
+                let index = self.graph.add_node(kind, self.new_node_position);
+                self.selected_node = Some(index);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn draw_graph(&self, ui: &imgui::Ui, origin: [f32; 2], size: [f32; 2], offset: [f32; 2], mouse: [f32; 2]) {
+        let draw_list = ui.get_window_draw_list();
+        let max = [origin[0] + size[0], origin[1] + size[1]];
+
+        draw_list.add_rect(origin, max, CANVAS_COLOR).filled(true).build();
+        let mut x = origin[0] + self.scrolling[0].rem_euclid(GRID_STEP);
+        while x < max[0] {
+            draw_list.add_line([x, origin[1]], [x, max[1]], GRID_COLOR).build();
+            x += GRID_STEP;
+        }
+        let mut y = origin[1] + self.scrolling[1].rem_euclid(GRID_STEP);
+        while y < max[1] {
+            draw_list.add_line([origin[0], y], [max[0], y], GRID_COLOR).build();
+            y += GRID_STEP;
+        }
+
+        let nodes = self.graph.get_nodes();
+        let draw_link = |from: [f32; 2], to: [f32; 2]| {
+            draw_list.add_bezier_curve(
+                from, [from[0] + LINK_CURVATURE, from[1]], [to[0] - LINK_CURVATURE, to[1]], to, LINK_COLOR
+            ).thickness(2.0).build();
+        };
+
+        for node in nodes {
+            let min = to_screen(node.position, offset);
+            for (i, input) in node.inputs.iter().enumerate() {
+                if let Some(from) = input.link {
+                    let from = get_output_socket(to_screen(nodes[from].position, offset));
+                    draw_link(from, get_input_socket(min, i));
+                }
+            }
+        }
+        if let Interaction::Linking(from) = self.interaction {
+            draw_link(get_output_socket(to_screen(nodes[from].position, offset)), mouse);
+        }
+
+        for (index, node) in nodes.iter().enumerate() {
+            let min = to_screen(node.position, offset);
+            let size = get_node_size(node);
+            let max = [min[0] + size[0], min[1] + size[1]];
+
+            let header_color = if index == OUTPUT_NODE_INDEX { OUTPUT_HEADER_COLOR } else { HEADER_COLOR };
+            let border_color = if self.selected_node == Some(index) { SELECTED_BORDER_COLOR } else { BORDER_COLOR };
+
+            draw_list.add_rect(min, max, NODE_COLOR).filled(true).rounding(4.0).build();
+            draw_list.add_rect(min, [max[0], min[1] + HEADER_HEIGHT], header_color)
+                .filled(true)
+                .rounding(4.0)
+                .round_bot_left(false)
+                .round_bot_right(false)
+                .build();
+            draw_list.add_rect(min, max, border_color).rounding(4.0).build();
+            draw_list.add_text([min[0] + 8.0, min[1] + 4.0], TEXT_COLOR, node.kind.get_name());
+
+            for (i, socket) in node.kind.get_inputs().iter().enumerate() {
+                let center = get_input_socket(min, i);
+                draw_list.add_circle(center, SOCKET_RADIUS, SOCKET_COLOR).filled(true).build();
+                draw_list.add_text([center[0] + 10.0, center[1] - 7.0], TEXT_COLOR, socket.name);
+            }
+            if node.kind.has_output() {
+                draw_list.add_circle(get_output_socket(min), SOCKET_RADIUS, SOCKET_COLOR).filled(true).build();
+            }
+        }
+    }
+
+    fn draw_properties(&mut self, ui: &imgui::Ui) -> bool {
+        let mut modified = false;
+
+        let index = match self.selected_node {
+            Some(index) => index,
+            None => {
+                ui.text_disabled("Select a node to edit it, right click the canvas to add nodes");
+                return false;
+            }
+        };
+        let node = match self.graph.get_mut_node(index) {
+            Some(node) => node,
+            None => return false
+        };
+        let _id = ui.push_id_usize(index);
+
+        ui.text(format!("{} node", node.kind.get_name()));
+
+        match &mut node.kind {
+            NodeKind::Noise { seed, octaves_count } => {
+                modified |= imgui::Drag::new("Seed").build(ui, seed);
+                modified |= ui.slider("Octaves", 1, 8, octaves_count);
+            },
+            NodeKind::Math(operation) => {
+                let mut i = MathOperation::ALL.iter().position(|o| o == operation).unwrap_or(0);
+                if ui.combo("Operation", &mut i, &MathOperation::ALL, |o| o.get_name().into()) {
+                    *operation = MathOperation::ALL[i];
+                    modified = true;
+                }
+            },
+            NodeKind::ColorRamp(ramp) => {
+                modified |= edit_color_ramp_stops(ui, &mut ramp.stops);
+            },
+            NodeKind::Texture { path, texture } => {
+                ui.input_text("Path", path)
+                    .hint("path/to/texture.png")
+                    .build();
+
+                if ui.button("Load") {
+                    match load_image(path) {
+                        Ok(image) => {
+                            *texture = Some(Texture::new(image));
+                            self.error = None;
+                        },
+                        Err(err) => {
+                            *texture = None;
+                            self.error = Some(err);
+                        }
+                    }
+                    modified = true;
+                }
+            },
+            _ => ()
+        }
+
+        modified |= draw_input_values(ui, node);
+
+        if index != OUTPUT_NODE_INDEX && ui.button("Remove node") {
+            self.graph.remove_node(index);
+            self.selected_node = None;
+            modified = true;
+        }
+
+        modified
+    }
+}
+
+impl Default for MaterialGraphEditor {
+    fn default() -> Self {
+        MaterialGraphEditor::new()
+    }
+}
+
+/// Edit values of the unlinked inputs of the node.
+fn draw_input_values(ui: &imgui::Ui, node: &mut GraphNode) -> bool {
+    let mut modified = false;
+
+    for (input, socket) in node.inputs.iter_mut().zip(node.kind.get_inputs()) {
+        if input.link.is_some() {
+            ui.text_disabled(format!("{} is linked", socket.name));
+            continue;
+        }
+
+        match socket.socket_type {
+            SocketType::Scalar => {
+                let mut value = input.value.x;
+                if drag_float(ui, socket.name, &mut value) {
+                    input.value = Vector3::new(value, value, value);
+                    modified = true;
+                }
+            },
+            SocketType::Color => {
+                let mut color: [f32; 3] = input.value.into();
+                if ui.color_edit3(socket.name, &mut color) {
+                    input.value = Vector3::from(color);
+                    modified = true;
+                }
+            },
+            SocketType::Position => ui.text_disabled(format!("{} is the hit position", socket.name)),
+            SocketType::Uv => ui.text_disabled(format!("{} is the hit UV", socket.name))
+        }
+    }
+
+    modified
+}
+
+fn to_screen(position: [f32; 2], offset: [f32; 2]) -> [f32; 2] {
+    [offset[0] + position[0], offset[1] + position[1]]
+}
+
+fn get_node_size(node: &GraphNode) -> [f32; 2] {
+    [NODE_WIDTH, HEADER_HEIGHT + ROW_HEIGHT * node.inputs.len() as f32 + SOCKET_RADIUS]
+}
+
+fn get_input_socket(node_min: [f32; 2], index: usize) -> [f32; 2] {
+    [node_min[0], node_min[1] + HEADER_HEIGHT + ROW_HEIGHT * (index as f32 + 0.5)]
+}
+
+fn get_output_socket(node_min: [f32; 2]) -> [f32; 2] {
+    [node_min[0] + NODE_WIDTH, node_min[1] + HEADER_HEIGHT * 0.5]
+}
+
+/// Check whether the point is close enough to the socket to grab it.
+fn is_near(point: [f32; 2], socket: [f32; 2]) -> bool {
+    let dx = point[0] - socket[0];
+    let dy = point[1] - socket[1];
+    dx * dx + dy * dy <= (SOCKET_RADIUS * 2.0) * (SOCKET_RADIUS * 2.0)
+}
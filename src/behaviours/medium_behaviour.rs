@@ -0,0 +1,63 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{rendering::{renderer::Renderer, medium::{Medium, DEFAULT_MEDIUM_DENSITY, DEFAULT_ANISOTROPY, DEFAULT_MEDIUM_STEPS_COUNT}}, ui::ImguiEditor};
+
+use super::Behaviour;
+
+pub struct MediumBehaviour {
+    renderer: Rc<RefCell<Renderer>>,
+    is_enabled: bool,
+    density: f32,
+    anisotropy: f32,
+    steps_count: u32
+}
+
+impl MediumBehaviour {
+    pub fn new(renderer: Rc<RefCell<Renderer>>) -> MediumBehaviour {
+        let mut behaviour = MediumBehaviour {
+            renderer,
+            is_enabled: false,
+            density: DEFAULT_MEDIUM_DENSITY,
+            anisotropy: DEFAULT_ANISOTROPY,
+            steps_count: DEFAULT_MEDIUM_STEPS_COUNT
+        };
+
+        behaviour.update_medium();
+
+        behaviour
+    }
+
+    fn update_medium(&mut self) {
+        let medium = if self.is_enabled {
+            Some(Medium {
+                density: self.density,
+                anisotropy: self.anisotropy,
+                steps_count: self.steps_count
+            })
+        } else {
+            None
+        };
+        self.renderer.borrow_mut().set_medium(medium);
+    }
+}
+
+impl Behaviour for MediumBehaviour {
+    fn update(&mut self, _delta_time: f32) {}
+}
+
+impl ImguiEditor for MediumBehaviour {
+    fn draw_ui(&mut self, ui: &imgui::Ui) {
+        let mut modified = ui.checkbox("Light scattering", &mut self.is_enabled);
+
+        ui.disabled(!self.is_enabled, || {
+            modified |= ui.slider("Density", 0.0, 1.0, &mut self.density);
+            modified |= ui.slider("Anisotropy", -0.95, 0.95, &mut self.anisotropy);
+            modified |= ui.slider("Steps", 1, 128, &mut self.steps_count);
+        });
+        ui.text_disabled("Every step casts a shadow ray per light");
+
+        if modified {
+            self.update_medium();
+        }
+    }
+}
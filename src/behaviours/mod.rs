@@ -2,7 +2,11 @@ pub mod camera_behaviour;
 pub mod renderer_behaviour;
 pub mod raycaster_behaviour;
 pub mod material_behaviour;
+pub mod material_graph_editor;
 pub mod lightning_behaviour;
+pub mod background_behaviour;
+pub mod fog_behaviour;
+pub mod medium_behaviour;
 
 pub trait Behaviour {
     fn update(&mut self, delta_time: f32);
@@ -1,9 +1,9 @@
 use std::{cell::RefCell, rc::Rc};
 
 use crate::raycaster::{BoxRaycaster, SphereRaycaster, Raycaster};
-use crate::rendering::renderer::Renderer;
+use crate::rendering::renderer::{Renderer, DEFAULT_GROUND_HEIGHT};
 use crate::utils::vector::{Vector3, ZERO_VECTOR};
-use crate::ui::{ImguiEditor, drag_float3};
+use crate::ui::{ImguiEditor, drag_float3, drag_float};
 
 use super::Behaviour;
 
@@ -18,7 +18,10 @@ pub struct RaycasterBehaviour {
     is_static: bool,
     position: Vector3,
     rotation: Vector3,
-    angular_velocity: Vector3
+    angular_velocity: Vector3,
+    show_ground_plane: bool,
+    ground_height: f32,
+    dirty_flag: bool
 }
 
 impl RaycasterBehaviour {
@@ -29,7 +32,10 @@ impl RaycasterBehaviour {
             is_static: false,
             position: ZERO_VECTOR,
             rotation: ZERO_VECTOR,
-            angular_velocity: Vector3::new(10.0, 10.0, 0.0)
+            angular_velocity: Vector3::new(10.0, 10.0, 0.0),
+            show_ground_plane: true,
+            ground_height: DEFAULT_GROUND_HEIGHT,
+            dirty_flag: true
         };
 
         behaviour.update_raycaster_type();
@@ -48,14 +54,22 @@ impl RaycasterBehaviour {
         };
         
         self.renderer.borrow_mut().set_raycaster(raycaster);
+        self.dirty_flag = true;
     }
 }
 
 impl Behaviour for RaycasterBehaviour {
     fn update(&mut self, delta_time: f32) {
-        if !self.is_static {
+        if !self.is_static && self.angular_velocity != ZERO_VECTOR {
             self.rotation += self.angular_velocity * delta_time;
+            self.dirty_flag = true;
+        }
+
+        // Scene is touched only on changes, so the path tracer could keep accumulating samples
+        if !self.dirty_flag {
+            return;
         }
+        self.dirty_flag = false;
 
         let mut renderer = self.renderer.borrow_mut();
         let raycaster = renderer.get_mut_raycaster();
@@ -63,6 +77,12 @@ impl Behaviour for RaycasterBehaviour {
 
         transform.set_position(&self.position);
         transform.set_rotation(&self.rotation);
+
+        let ground = renderer.get_mut_ground_plane();
+        ground.is_visible = self.show_ground_plane;
+        ground.raycaster.get_mut_tranform().set_position(
+            &Vector3::new(0.0, self.ground_height, 0.0)
+        );
     }
 }
 
@@ -77,13 +97,24 @@ impl ImguiEditor for RaycasterBehaviour {
             self.update_raycaster_type();
         }
 
+        let mut modified = false;
+
         ui.checkbox("Is static", &mut self.is_static);
-        drag_float3(ui, "Position", &mut self.position);
+        modified |= drag_float3(ui, "Position", &mut self.position);
         if !self.is_static {
             drag_float3(ui, "Angular velocity", &mut self.angular_velocity);
         }
         ui.disabled(!self.is_static, || {
-            drag_float3(ui, "Rotation", &mut self.rotation);
+            modified |= drag_float3(ui, "Rotation", &mut self.rotation);
         });
+
+        ui.spacing();
+
+        modified |= ui.checkbox("Ground plane", &mut self.show_ground_plane);
+        ui.disabled(!self.show_ground_plane, || {
+            modified |= drag_float(ui, "Ground height", &mut self.ground_height);
+        });
+
+        self.dirty_flag |= modified;
     }
 }
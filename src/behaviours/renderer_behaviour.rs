@@ -1,6 +1,6 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::{rendering::renderer::Renderer, ui::ImguiEditor, app};
+use crate::{rendering::renderer::{Renderer, RenderMode, DEFAULT_MAX_DEPTH}, ui::ImguiEditor, app};
 
 use super::Behaviour;
 
@@ -12,6 +12,8 @@ enum RenderQuality {
 pub struct RendererBehaviour {
     renderer: Rc<RefCell<Renderer>>,
     render_quality: RenderQuality,
+    render_mode: RenderMode,
+    max_depth: u32,
     delta_time: f32
 }
 
@@ -20,6 +22,8 @@ impl RendererBehaviour {
         let mut behaviour = RendererBehaviour {
             renderer,
             render_quality: RenderQuality::HalfQuality,
+            render_mode: RenderMode::DirectLighting,
+            max_depth: DEFAULT_MAX_DEPTH,
             delta_time: 0.0
         };
 
@@ -54,6 +58,29 @@ impl Behaviour for RendererBehaviour {
 impl ImguiEditor for RendererBehaviour {
     fn draw_ui(&mut self, ui: &imgui::Ui) {
         ui.text(format!("{:.1} FPS", 1.0 / self.delta_time));
+        ui.text(format!(
+            "{} rays per frame", self.renderer.borrow().get_frame_rays_count()
+        ));
+        if self.render_mode == RenderMode::PathTraced {
+            ui.text(format!(
+                "{} samples per pixel", self.renderer.borrow().get_samples_count()
+            ));
+        }
+
+        ui.spacing();
+
+        ui.text("Render mode");
+        if ui.radio_button(
+            "Direct lighting", &mut self.render_mode, RenderMode::DirectLighting
+        ) {
+            self.renderer.borrow_mut().set_render_mode(self.render_mode);
+        }
+        ui.same_line();
+        if ui.radio_button(
+            "Path traced", &mut self.render_mode, RenderMode::PathTraced
+        ) {
+            self.renderer.borrow_mut().set_render_mode(self.render_mode);
+        }
 
         ui.spacing();
 
@@ -81,5 +108,11 @@ impl ImguiEditor for RendererBehaviour {
         ) {
             self.update_renderer();
         }
+
+        ui.spacing();
+
+        if ui.slider("Max depth", 0, 8, &mut self.max_depth) {
+            self.renderer.borrow_mut().set_max_depth(self.max_depth);
+        }
     }
 }
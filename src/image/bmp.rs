@@ -0,0 +1,123 @@
+use crate::utils::color::Color;
+
+use super::Image;
+
+/// Compression values of the BMP info header.
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+pub fn is_bmp(data: &[u8]) -> bool {
+    data.starts_with(b"BM")
+}
+
+/// Decode uncompressed Windows bitmap with 1, 4, 8, 16, 24 or 32 bits per pixel,
+/// stored either bottom-up or top-down.
+pub fn decode_bmp(data: &[u8]) -> Result<Image<Color>, String> {
+    if !is_bmp(data) {
+        return Err("missing BMP signature".to_string());
+    }
+
+    let pixels_offset = read_u32(data, 10)? as usize;
+    let header_size = read_u32(data, 14)? as usize;
+    if header_size < 40 {
+        return Err(format!("unsupported BMP header of size {}", header_size));
+    }
+
+    let width = read_u32(data, 18)? as i32;
+    let height = read_u32(data, 22)? as i32;
+    let bits_per_pixel = read_u16(data, 28)?;
+    let compression = read_u32(data, 30)?;
+    let colors_count = read_u32(data, 46)? as usize;
+
+    if width <= 0 || height == 0 {
+        return Err("image is empty".to_string());
+    }
+    // Negative height means the rows are stored from the top
+    let is_top_down = height < 0;
+    let width = width as usize;
+    let height = height.unsigned_abs() as usize;
+
+    let masks = match (compression, bits_per_pixel) {
+        (BI_RGB, 16) => [0x7C00, 0x03E0, 0x001F, 0],
+        (BI_RGB, 32) => [0x00FF0000, 0x0000FF00, 0x000000FF, 0],
+        (BI_RGB, 1 | 4 | 8 | 24) => [0; 4],
+        (BI_BITFIELDS | BI_ALPHABITFIELDS, 16 | 32) => {
+            // Masks follow the info header, or are a part of its newer versions
+            let alpha_mask = if header_size >= 56 || compression == BI_ALPHABITFIELDS {
+                read_u32(data, 14 + 52)?
+            } else {
+                0
+            };
+            [read_u32(data, 14 + 40)?, read_u32(data, 14 + 44)?, read_u32(data, 14 + 48)?, alpha_mask]
+        },
+        (BI_RGB, _) => return Err(format!("unsupported bit depth {}", bits_per_pixel)),
+        _ => return Err(format!("unsupported compression method {}", compression))
+    };
+
+    let palette = if bits_per_pixel <= 8 {
+        let count = if colors_count == 0 { 1 << bits_per_pixel } else { colors_count };
+        let offset = 14 + header_size;
+        let entries = data.get(offset..offset + count.min(256) * 4).ok_or("palette is truncated")?;
+        entries.chunks(4).map(|entry| Color::new(entry[2], entry[1], entry[0])).collect()
+    } else {
+        Vec::new()
+    };
+
+    // Rows are padded to multiples of 4 bytes
+    let stride = (width * bits_per_pixel as usize).div_ceil(32) * 4;
+    let end = stride.checked_mul(height)
+        .and_then(|size| size.checked_add(pixels_offset))
+        .ok_or("image is too large")?;
+    let rows = data.get(pixels_offset..end).ok_or("pixel data is truncated")?;
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let row_index = if is_top_down { y } else { height - 1 - y };
+        let row = &rows[row_index * stride..(row_index + 1) * stride];
+
+        for x in 0..width {
+            let color = match bits_per_pixel {
+                1 | 4 | 8 => {
+                    let bit = x * bits_per_pixel as usize;
+                    let shift = 8 - bits_per_pixel as usize - bit % 8;
+                    let index = (row[bit / 8] >> shift) as usize & ((1 << bits_per_pixel) - 1);
+                    *palette.get(index).ok_or("palette index out of range")?
+                },
+                24 => Color::new(row[x * 3 + 2], row[x * 3 + 1], row[x * 3]),
+                16 => decode_masked(u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]) as u32, &masks),
+                _ => {
+                    let i = x * 4;
+                    decode_masked(u32::from_le_bytes([row[i], row[i + 1], row[i + 2], row[i + 3]]), &masks)
+                }
+            };
+            pixels.push(color);
+        }
+    }
+
+    return Ok(Image::new(width, height, pixels));
+}
+
+/// Extract channels of a pixel by the red, green, blue and alpha bit masks.
+/// Pixels without alpha mask are opaque.
+fn decode_masked(value: u32, masks: &[u32; 4]) -> Color {
+    let channel = |mask: u32| {
+        if mask == 0 {
+            return 255;
+        }
+        let max_value = mask >> mask.trailing_zeros();
+        (((value & mask) >> mask.trailing_zeros()) as u64 * 255 / max_value as u64) as u8
+    };
+
+    Color::new_with_alpha(channel(masks[0]), channel(masks[1]), channel(masks[2]), channel(masks[3]))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, String> {
+    let bytes = data.get(offset..offset + 2).ok_or("BMP header is truncated")?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, String> {
+    let bytes = data.get(offset..offset + 4).ok_or("BMP header is truncated")?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
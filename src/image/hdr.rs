@@ -0,0 +1,168 @@
+use crate::utils::color::HdrColor;
+
+use super::Image;
+
+pub fn is_hdr(data: &[u8]) -> bool {
+    data.starts_with(b"#?RADIANCE") || data.starts_with(b"#?RGBE")
+}
+
+/// Decode Radiance HDR image, which stores pixels in the shared exponent RGBE format.
+/// Both flat and run length encoded scanlines are supported.
+pub fn decode_hdr(data: &[u8]) -> Result<Image<HdrColor>, String> {
+    let mut reader = Reader { data, offset: 0 };
+
+    let signature = reader.read_line()?;
+    if signature != "#?RADIANCE" && signature != "#?RGBE" {
+        return Err("missing Radiance HDR signature".to_string());
+    }
+
+    // Header lines end with an empty line
+    loop {
+        let line = reader.read_line()?;
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != "32-bit_rle_rgbe" {
+                return Err(format!("unsupported pixel format {}", format));
+            }
+        }
+    }
+
+    let (width, height) = parse_resolution(&reader.read_line()?)?;
+    let pixels_count = width.checked_mul(height).ok_or("image is too large")?;
+
+    // Check the size before allocating, each scanline takes at least a few bytes even when compressed
+    let scanline_size = if is_rle_width(width) {
+        (width / 32).max(4)
+    } else {
+        width.checked_mul(4).ok_or("image is too large")?
+    };
+    if height.checked_mul(scanline_size).is_none_or(|size| size > reader.get_remaining()) {
+        return Err("image is larger than the file data".to_string());
+    }
+
+    let mut pixels = Vec::with_capacity(pixels_count);
+    let mut scanline = vec![[0u8; 4]; width];
+    for _ in 0..height {
+        reader.read_scanline(&mut scanline)?;
+        pixels.extend(scanline.iter().map(rgbe_to_color));
+    }
+
+    return Ok(Image::new(width, height, pixels));
+}
+
+/// Parse resolution line. Only the standard orientation with rows going down is supported.
+fn parse_resolution(line: &str) -> Result<(usize, usize), String> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    if parts.len() != 4 || parts[0] != "-Y" || parts[2] != "+X" {
+        return Err(format!("unsupported resolution line {}", line));
+    }
+
+    let height = parts[1].parse::<usize>().map_err(|_| format!("invalid height {}", parts[1]))?;
+    let width = parts[3].parse::<usize>().map_err(|_| format!("invalid width {}", parts[3]))?;
+    if width == 0 || height == 0 {
+        return Err("image is empty".to_string());
+    }
+
+    return Ok((width, height));
+}
+
+/// Check whether scanlines of this width may be run length encoded.
+fn is_rle_width(width: usize) -> bool {
+    (8..0x8000).contains(&width)
+}
+
+fn rgbe_to_color(rgbe: &[u8; 4]) -> HdrColor {
+    if rgbe[3] == 0 {
+        return HdrColor::new(0.0, 0.0, 0.0);
+    }
+
+    let scale = 2.0_f32.powi(rgbe[3] as i32 - (128 + 8));
+    HdrColor::new(
+        rgbe[0] as f32 * scale,
+        rgbe[1] as f32 * scale,
+        rgbe[2] as f32 * scale
+    )
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize
+}
+
+impl<'a> Reader<'a> {
+    fn get_remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    fn read_byte(&mut self) -> Result<u8, String> {
+        let byte = *self.data.get(self.offset).ok_or("unexpected end of file")?;
+        self.offset += 1;
+        return Ok(byte);
+    }
+
+    fn read_line(&mut self) -> Result<String, String> {
+        let mut line = Vec::new();
+        loop {
+            match self.read_byte()? {
+                b'\n' => break,
+                byte => line.push(byte)
+            }
+        }
+        return String::from_utf8(line).map_err(|_| "header is not valid text".to_string());
+    }
+
+    fn read_scanline(&mut self, scanline: &mut [[u8; 4]]) -> Result<(), String> {
+        let width = scanline.len();
+        let mut first = [0u8; 4];
+        for byte in first.iter_mut() {
+            *byte = self.read_byte()?;
+        }
+
+        // Run length encoded scanlines start with two bytes of 2 followed by the width
+        let is_rle = is_rle_width(width) && first[0] == 2 && first[1] == 2 && first[2] & 0x80 == 0;
+        if !is_rle {
+            scanline[0] = first;
+            for pixel in scanline.iter_mut().skip(1) {
+                for byte in pixel.iter_mut() {
+                    *byte = self.read_byte()?;
+                }
+            }
+            return Ok(());
+        }
+
+        if ((first[2] as usize) << 8 | first[3] as usize) != width {
+            return Err("scanline width does not match the image width".to_string());
+        }
+
+        // Each channel is encoded separately as a sequence of runs and literal spans
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = self.read_byte()? as usize;
+                if count > 128 {
+                    let count = count - 128;
+                    if x + count > width {
+                        return Err("run overflows the scanline".to_string());
+                    }
+                    let value = self.read_byte()?;
+                    for pixel in &mut scanline[x..x + count] {
+                        pixel[channel] = value;
+                    }
+                    x += count;
+                } else {
+                    if count == 0 || x + count > width {
+                        return Err("invalid span length in scanline".to_string());
+                    }
+                    for pixel in &mut scanline[x..x + count] {
+                        pixel[channel] = self.read_byte()?;
+                    }
+                    x += count;
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,309 @@
+/// Maximum length of Huffman codes in deflate streams.
+const MAX_CODE_LENGTH: usize = 15;
+
+/// Base lengths and extra bits of the length codes 257..285.
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+];
+
+/// Base distances and extra bits of the distance codes 0..29.
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13
+];
+
+/// Order in which code lengths of the code length alphabet are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Decompress zlib stream (RFC 1950) and verify its checksum.
+/// Fails if the decompressed data is longer than the maximum size.
+pub fn zlib_decompress(data: &[u8], max_size: usize) -> Result<Vec<u8>, String> {
+    if data.len() < 6 {
+        return Err("zlib stream is too short".to_string());
+    }
+
+    let cmf = data[0];
+    let flags = data[1];
+    if cmf & 0x0F != 8 || cmf >> 4 > 7 {
+        return Err("zlib stream does not use deflate compression".to_string());
+    }
+    if !((cmf as u16) << 8 | flags as u16).is_multiple_of(31) {
+        return Err("corrupt zlib header".to_string());
+    }
+    if flags & 0x20 != 0 {
+        return Err("zlib preset dictionaries are not supported".to_string());
+    }
+
+    let mut reader = BitReader { data: &data[2..], offset: 0, bit_buffer: 0, bits_count: 0 };
+    let output = inflate(&mut reader, max_size)?;
+
+    let checksum_offset = 2 + reader.get_byte_offset();
+    let checksum = data.get(checksum_offset..checksum_offset + 4).ok_or("missing zlib checksum")?;
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&output) {
+        return Err("zlib checksum mismatch".to_string());
+    }
+
+    return Ok(output);
+}
+
+/// Decompress raw deflate stream (RFC 1951).
+fn inflate(reader: &mut BitReader, max_size: usize) -> Result<Vec<u8>, String> {
+    let mut output = Vec::new();
+
+    loop {
+        let is_final = reader.read_bits(1)? == 1;
+
+        match reader.read_bits(2)? {
+            0 => {
+                reader.align_to_byte();
+                let length = reader.read_bits(16)?;
+                let inverted_length = reader.read_bits(16)?;
+                if length != !inverted_length & 0xFFFF {
+                    return Err("corrupt stored block length".to_string());
+                }
+                check_size(&output, length as usize, max_size)?;
+                for _ in 0..length {
+                    output.push(reader.read_bits(8)? as u8);
+                }
+            },
+            1 => {
+                let (literals, distances) = build_fixed_codes();
+                inflate_block(reader, &mut output, max_size, &literals, &distances)?;
+            },
+            2 => {
+                let (literals, distances) = read_dynamic_codes(reader)?;
+                inflate_block(reader, &mut output, max_size, &literals, &distances)?;
+            },
+            _ => return Err("invalid deflate block type".to_string())
+        }
+
+        if is_final {
+            break;
+        }
+    }
+
+    return Ok(output);
+}
+
+fn inflate_block(
+    reader: &mut BitReader, output: &mut Vec<u8>, max_size: usize, literals: &Huffman, distances: &Huffman
+) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+
+        if symbol < 256 {
+            check_size(output, 1, max_size)?;
+            output.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+
+        let index = symbol - 257;
+        if index >= LENGTH_BASES.len() {
+            return Err("invalid length code".to_string());
+        }
+        let length = LENGTH_BASES[index] as usize + reader.read_bits(LENGTH_EXTRA_BITS[index])? as usize;
+
+        let index = distances.decode(reader)? as usize;
+        if index >= DISTANCE_BASES.len() {
+            return Err("invalid distance code".to_string());
+        }
+        let distance = DISTANCE_BASES[index] as usize + reader.read_bits(DISTANCE_EXTRA_BITS[index])? as usize;
+        if distance > output.len() {
+            return Err("distance points before the start of the output".to_string());
+        }
+
+        check_size(output, length, max_size)?;
+
+        // Copy byte by byte, since the copied span may overlap the bytes being written
+        let start = output.len() - distance;
+        for i in 0..length {
+            output.push(output[start + i]);
+        }
+    }
+}
+
+/// Check that appending the count of bytes keeps the output within the maximum size.
+fn check_size(output: &[u8], count: usize, max_size: usize) -> Result<(), String> {
+    if output.len() + count > max_size {
+        return Err("decompressed data is longer than expected".to_string());
+    }
+    return Ok(());
+}
+
+fn build_fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[0..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..288].fill(8);
+
+    let literals = Huffman::new(&lengths).expect("Fixed literal codes must be valid");
+    let distances = Huffman::new(&[5; 30]).expect("Fixed distance codes must be valid");
+    return (literals, distances);
+}
+
+fn read_dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let literals_count = reader.read_bits(5)? as usize + 257;
+    let distances_count = reader.read_bits(5)? as usize + 1;
+    let code_lengths_count = reader.read_bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(code_lengths_count) {
+        code_lengths[index] = reader.read_bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_lengths)?;
+
+    // Lengths of both alphabets are encoded as one sequence, runs may cross between them
+    let mut lengths = Vec::with_capacity(literals_count + distances_count);
+    while lengths.len() < literals_count + distances_count {
+        let (value, repeat) = match code_lengths.decode(reader)? {
+            length @ 0..=15 => (length as u8, 1),
+            16 => {
+                let previous = *lengths.last().ok_or("repeated code length has no predecessor")?;
+                (previous, 3 + reader.read_bits(2)?)
+            },
+            17 => (0, 3 + reader.read_bits(3)?),
+            _ => (0, 11 + reader.read_bits(7)?)
+        };
+
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+    if lengths.len() != literals_count + distances_count {
+        return Err("code lengths overflow the alphabets".to_string());
+    }
+    if lengths[256] == 0 {
+        return Err("missing end of block code".to_string());
+    }
+
+    let literals = Huffman::new(&lengths[..literals_count])?;
+    let distances = Huffman::new(&lengths[literals_count..])?;
+    return Ok((literals, distances));
+}
+
+/// Canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+    /// Count of codes of each length.
+    counts: [u16; MAX_CODE_LENGTH + 1],
+    /// Symbols ordered by their codes.
+    symbols: Vec<u16>
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Huffman, String> {
+        let mut counts = [0u16; MAX_CODE_LENGTH + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Check that there are not more codes of some length than it can hold
+        let mut available = 1i32;
+        for &count in counts.iter().skip(1) {
+            available = available * 2 - count as i32;
+            if available < 0 {
+                return Err("oversubscribed Huffman code".to_string());
+            }
+        }
+
+        let mut offsets = [0u16; MAX_CODE_LENGTH + 2];
+        for length in 1..=MAX_CODE_LENGTH {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0u16; offsets[MAX_CODE_LENGTH + 1] as usize];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        return Ok(Huffman { counts, symbols });
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        // Codes of each length follow right after the codes of the previous length
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+
+        for &count in self.counts.iter().skip(1) {
+            code |= reader.read_bits(1)? as i32;
+            let count = count as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        return Err("invalid Huffman code".to_string());
+    }
+}
+
+/// Reader of bits packed starting from the least significant bit of each byte.
+struct BitReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    bit_buffer: u32,
+    bits_count: u8
+}
+
+impl<'a> BitReader<'a> {
+    fn read_bits(&mut self, count: u8) -> Result<u32, String> {
+        while self.bits_count < count {
+            let byte = *self.data.get(self.offset).ok_or("unexpected end of compressed data")?;
+            self.offset += 1;
+            self.bit_buffer |= (byte as u32) << self.bits_count;
+            self.bits_count += 8;
+        }
+
+        let value = self.bit_buffer & ((1u64 << count) - 1) as u32;
+        self.bit_buffer >>= count;
+        self.bits_count -= count;
+        return Ok(value);
+    }
+
+    fn align_to_byte(&mut self) {
+        self.bit_buffer = 0;
+        self.bits_count = 0;
+    }
+
+    /// Get offset of the first byte, which was not consumed.
+    fn get_byte_offset(&self) -> usize {
+        self.offset - (self.bits_count / 8) as usize
+    }
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MODULO: u32 = 65521;
+
+    let mut a = 1u32;
+    let mut b = 0u32;
+    // Sums of this many bytes can not overflow before taking the modulo
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MODULO;
+        b %= MODULO;
+    }
+
+    return b << 16 | a;
+}
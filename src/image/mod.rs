@@ -0,0 +1,81 @@
+use std::fs;
+
+use crate::utils::color::{Color, HdrColor};
+
+pub mod hdr;
+pub mod inflate;
+pub mod png;
+pub mod bmp;
+pub mod ppm;
+
+/// Two dimensional grid of pixels stored row by row, from the top left corner.
+#[derive(Clone)]
+pub struct Image<T> {
+    width: usize,
+    height: usize,
+    pixels: Vec<T>
+}
+
+impl<T: Copy> Image<T> {
+    pub fn new(width: usize, height: usize, pixels: Vec<T>) -> Image<T> {
+        assert_eq!(pixels.len(), width * height, "Pixels count must match image size");
+        Image { width, height, pixels }
+    }
+
+    pub fn get_width(&self) -> usize { self.width }
+
+    pub fn get_height(&self) -> usize { self.height }
+
+    pub fn get_pixels(&self) -> &[T] {
+        &self.pixels
+    }
+
+    #[inline]
+    pub fn get_pixel(&self, x: usize, y: usize) -> T {
+        self.pixels[y * self.width + x]
+    }
+}
+
+/// Load image with floating point channels. Images with 8 bit channels are
+/// converted, so they cover only the [0, 1] range.
+pub fn load_hdr_image(path: &str) -> Result<Image<HdrColor>, String> {
+    let data = read_file(path)?;
+    decode_hdr_image(&data).map_err(|err| format!("Failed to decode {}: {}", path, err))
+}
+
+/// Load image with 8 bit channels, HDR images are clamped to the displayable range.
+pub fn load_image(path: &str) -> Result<Image<Color>, String> {
+    let data = read_file(path)?;
+    decode_image(&data).map_err(|err| format!("Failed to decode {}: {}", path, err))
+}
+
+/// Decode PNG, BMP, PPM or Radiance HDR image, recognized by its signature.
+pub fn decode_image(data: &[u8]) -> Result<Image<Color>, String> {
+    if png::is_png(data) {
+        png::decode_png(data)
+    } else if bmp::is_bmp(data) {
+        bmp::decode_bmp(data)
+    } else if ppm::is_ppm(data) {
+        ppm::decode_ppm(data)
+    } else if hdr::is_hdr(data) {
+        let image = hdr::decode_hdr(data)?;
+        let pixels = image.get_pixels().iter().map(|pixel| Color::from(*pixel)).collect();
+        Ok(Image::new(image.get_width(), image.get_height(), pixels))
+    } else {
+        Err("unknown image format".to_string())
+    }
+}
+
+fn decode_hdr_image(data: &[u8]) -> Result<Image<HdrColor>, String> {
+    if hdr::is_hdr(data) {
+        return hdr::decode_hdr(data);
+    }
+
+    let image = decode_image(data)?;
+    let pixels = image.get_pixels().iter().map(|pixel| HdrColor::from(*pixel)).collect();
+    return Ok(Image::new(image.get_width(), image.get_height(), pixels));
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|err| format!("Failed to read {}: {}", path, err))
+}
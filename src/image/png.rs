@@ -0,0 +1,324 @@
+use crate::utils::color::Color;
+
+use super::Image;
+use super::inflate::zlib_decompress;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Starting positions and steps of the seven Adam7 interlacing passes, as (x, y, dx, dy).
+const ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)
+];
+
+pub fn is_png(data: &[u8]) -> bool {
+    data.starts_with(&PNG_SIGNATURE)
+}
+
+/// Decode PNG image of any standard color type and bit depth, interlaced or not.
+/// Colors with 16 bits per channel are reduced to 8 bits.
+pub fn decode_png(data: &[u8]) -> Result<Image<Color>, String> {
+    if !is_png(data) {
+        return Err("missing PNG signature".to_string());
+    }
+
+    let mut header: Option<Header> = None;
+    let mut palette: Vec<Color> = Vec::new();
+    let mut transparency: Option<Vec<u8>> = None;
+    let mut compressed = Vec::new();
+
+    let mut offset = PNG_SIGNATURE.len();
+    loop {
+        let (chunk_type, chunk_data) = read_chunk(data, &mut offset)?;
+
+        match &chunk_type {
+            b"IHDR" => header = Some(Header::parse(chunk_data)?),
+            b"PLTE" => {
+                if chunk_data.len() % 3 != 0 || chunk_data.len() > 256 * 3 {
+                    return Err("invalid palette size".to_string());
+                }
+                palette = chunk_data.chunks(3).map(|c| Color::new(c[0], c[1], c[2])).collect();
+            },
+            b"tRNS" => transparency = Some(chunk_data.to_vec()),
+            b"IDAT" => compressed.extend_from_slice(chunk_data),
+            b"IEND" => break,
+            _ => {
+                // Critical chunks have an uppercase first letter and can not be skipped
+                if chunk_type[0].is_ascii_uppercase() {
+                    return Err(format!("unsupported critical chunk {}", String::from_utf8_lossy(&chunk_type)));
+                }
+            }
+        }
+    }
+
+    let header = header.ok_or("missing IHDR chunk")?;
+    if compressed.is_empty() {
+        return Err("missing IDAT chunk".to_string());
+    }
+
+    if header.color_type == 3 {
+        if palette.is_empty() {
+            return Err("missing palette of the indexed image".to_string());
+        }
+        // Palette transparency stores alpha of the first palette entries
+        if let Some(alpha) = &transparency {
+            for (color, &a) in palette.iter_mut().zip(alpha) {
+                color.a = a;
+            }
+        }
+    }
+
+    // Limit the decompressed data to the size of the scanlines and check it before allocating the pixels,
+    // so bogus headers and compressed data can not exhaust the memory
+    let data_size = header.get_data_size().ok_or("image is too large")?;
+    let raw = zlib_decompress(&compressed, data_size)?;
+    if raw.len() < data_size {
+        return Err("image data is shorter than the image".to_string());
+    }
+
+    let mut pixels = vec![Color::default(); header.width * header.height];
+    let mut offset = 0;
+    if header.is_interlaced {
+        for (x0, y0, dx, dy) in ADAM7_PASSES {
+            let pass_width = (header.width + dx - 1 - x0) / dx;
+            let pass_height = (header.height + dy - 1 - y0) / dy;
+            if pass_width == 0 || pass_height == 0 {
+                continue;
+            }
+
+            let samples = unfilter(&header, &raw, &mut offset, pass_width, pass_height)?;
+            for y in 0..pass_height {
+                for x in 0..pass_width {
+                    let color = header.get_color(&samples, pass_width, x, y, &palette, &transparency)?;
+                    pixels[(y0 + y * dy) * header.width + x0 + x * dx] = color;
+                }
+            }
+        }
+    } else {
+        let samples = unfilter(&header, &raw, &mut offset, header.width, header.height)?;
+        for y in 0..header.height {
+            for x in 0..header.width {
+                pixels[y * header.width + x] = header.get_color(&samples, header.width, x, y, &palette, &transparency)?;
+            }
+        }
+    }
+
+    return Ok(Image::new(header.width, header.height, pixels));
+}
+
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+    is_interlaced: bool
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Header, String> {
+        if data.len() != 13 {
+            return Err("invalid IHDR chunk size".to_string());
+        }
+
+        let width = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let height = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        let bit_depth = data[8];
+        let color_type = data[9];
+
+        if width == 0 || height == 0 {
+            return Err("image is empty".to_string());
+        }
+
+        let allowed_depths: &[u8] = match color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => return Err(format!("invalid color type {}", color_type))
+        };
+        if !allowed_depths.contains(&bit_depth) {
+            return Err(format!("invalid bit depth {} for color type {}", bit_depth, color_type));
+        }
+
+        if data[10] != 0 || data[11] != 0 {
+            return Err("unsupported compression or filter method".to_string());
+        }
+        if data[12] > 1 {
+            return Err("unsupported interlace method".to_string());
+        }
+
+        return Ok(Header { width, height, bit_depth, color_type, is_interlaced: data[12] == 1 });
+    }
+
+    fn get_channels_count(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1
+        }
+    }
+
+    fn get_bits_per_pixel(&self) -> usize {
+        self.get_channels_count() * self.bit_depth as usize
+    }
+
+    /// Get size of the filtered scanlines of all interlacing passes, each of them starts with a filter byte.
+    /// Returns None if the size does not fit in memory.
+    fn get_data_size(&self) -> Option<usize> {
+        let get_pass_size = |width: usize, height: usize| {
+            let stride = width.checked_mul(self.get_bits_per_pixel())?.div_ceil(8);
+            return (stride + 1).checked_mul(height);
+        };
+
+        if !self.is_interlaced {
+            return get_pass_size(self.width, self.height);
+        }
+
+        let mut size = 0usize;
+        for (x0, y0, dx, dy) in ADAM7_PASSES {
+            let pass_width = (self.width + dx - 1 - x0) / dx;
+            let pass_height = (self.height + dy - 1 - y0) / dy;
+            if pass_width > 0 && pass_height > 0 {
+                size = size.checked_add(get_pass_size(pass_width, pass_height)?)?;
+            }
+        }
+        return Some(size);
+    }
+
+    /// Read sample of the channel of the pixel in the unfiltered scanlines.
+    fn get_sample(&self, samples: &[u8], width: usize, x: usize, y: usize, channel: usize) -> u16 {
+        let stride = (width * self.get_bits_per_pixel()).div_ceil(8);
+        let row = &samples[y * stride..(y + 1) * stride];
+
+        match self.bit_depth {
+            8 => row[x * self.get_channels_count() + channel] as u16,
+            16 => {
+                let i = (x * self.get_channels_count() + channel) * 2;
+                u16::from_be_bytes([row[i], row[i + 1]])
+            },
+            depth => {
+                // Pixels narrower than a byte are packed from the most significant bit
+                let bit = x * depth as usize;
+                let shift = 8 - depth as usize - bit % 8;
+                ((row[bit / 8] >> shift) & ((1 << depth) - 1)) as u16
+            }
+        }
+    }
+
+    fn get_color(
+        &self, samples: &[u8], width: usize, x: usize, y: usize, palette: &[Color], transparency: &Option<Vec<u8>>
+    ) -> Result<Color, String> {
+        let sample = |channel| self.get_sample(samples, width, x, y, channel);
+        let max_value = (1u32 << self.bit_depth) - 1;
+        let to_byte = |value: u16| (value as u32 * 255 / max_value) as u8;
+
+        // Transparent color key of the grayscale and truecolor images, in the image bit depth
+        let key = |index: usize| transparency.as_ref()
+            .and_then(|t| t.get(index * 2..index * 2 + 2))
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]));
+
+        let color = match self.color_type {
+            0 => {
+                let gray = sample(0);
+                let alpha = if key(0) == Some(gray) { 0 } else { 255 };
+                Color::new_with_alpha(to_byte(gray), to_byte(gray), to_byte(gray), alpha)
+            },
+            2 => {
+                let (r, g, b) = (sample(0), sample(1), sample(2));
+                let is_key = key(0) == Some(r) && key(1) == Some(g) && key(2) == Some(b);
+                Color::new_with_alpha(to_byte(r), to_byte(g), to_byte(b), if is_key { 0 } else { 255 })
+            },
+            3 => *palette.get(sample(0) as usize).ok_or("palette index out of range")?,
+            4 => {
+                let gray = to_byte(sample(0));
+                Color::new_with_alpha(gray, gray, gray, to_byte(sample(1)))
+            },
+            _ => Color::new_with_alpha(to_byte(sample(0)), to_byte(sample(1)), to_byte(sample(2)), to_byte(sample(3)))
+        };
+
+        return Ok(color);
+    }
+}
+
+/// Read chunk at the offset and verify its checksum, returning its type and data.
+fn read_chunk<'a>(data: &'a [u8], offset: &mut usize) -> Result<([u8; 4], &'a [u8]), String> {
+    let header = data.get(*offset..*offset + 8).ok_or("unexpected end of file")?;
+    let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let chunk_type = [header[4], header[5], header[6], header[7]];
+
+    let end = (*offset + 8).checked_add(length).ok_or("invalid chunk length")?;
+    let chunk_data = data.get(*offset + 8..end).ok_or("unexpected end of file")?;
+    let checksum = data.get(end..end + 4).ok_or("unexpected end of file")?;
+
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != crc32(&data[*offset + 4..end]) {
+        return Err(format!("checksum mismatch in chunk {}", String::from_utf8_lossy(&chunk_type)));
+    }
+
+    *offset = end + 4;
+    return Ok((chunk_type, chunk_data));
+}
+
+/// Revert the per-scanline filters of an image (or of an interlacing pass) starting
+/// at the offset in the decompressed data.
+fn unfilter(header: &Header, raw: &[u8], offset: &mut usize, width: usize, height: usize) -> Result<Vec<u8>, String> {
+    let stride = (width * header.get_bits_per_pixel()).div_ceil(8);
+    // Filters work on whole bytes, pixels narrower than a byte use the previous byte
+    let pixel_size = header.get_bits_per_pixel().div_ceil(8);
+
+    let size = (stride + 1) * height;
+    let data = raw.get(*offset..*offset + size).ok_or("image data is shorter than the image")?;
+    *offset += size;
+
+    let mut samples = vec![0u8; stride * height];
+    for y in 0..height {
+        let filter = data[y * (stride + 1)];
+        let line = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+
+        let (previous_rows, current_rows) = samples.split_at_mut(y * stride);
+        let previous = if y > 0 { &previous_rows[(y - 1) * stride..] } else { &[][..] };
+        let current = &mut current_rows[..stride];
+
+        for i in 0..stride {
+            let a = if i >= pixel_size { current[i - pixel_size] } else { 0 };
+            let b = if y > 0 { previous[i] } else { 0 };
+            let c = if y > 0 && i >= pixel_size { previous[i - pixel_size] } else { 0 };
+
+            let predictor = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(format!("invalid filter type {}", filter))
+            };
+            current[i] = line[i].wrapping_add(predictor);
+        }
+    }
+
+    return Ok(samples);
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB88320 } else { crc >> 1 };
+        }
+    }
+    return !crc;
+}
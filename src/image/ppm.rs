@@ -0,0 +1,101 @@
+use crate::utils::color::Color;
+
+use super::Image;
+
+pub fn is_ppm(data: &[u8]) -> bool {
+    data.starts_with(b"P3") || data.starts_with(b"P6")
+}
+
+/// Decode Netpbm color image, either in the ASCII (P3) or the binary (P6) variant.
+pub fn decode_ppm(data: &[u8]) -> Result<Image<Color>, String> {
+    if !is_ppm(data) {
+        return Err("missing PPM signature".to_string());
+    }
+
+    let is_binary = data[1] == b'6';
+    let mut reader = Reader { data, offset: 2 };
+
+    let width = reader.read_number("width")?;
+    let height = reader.read_number("height")?;
+    let max_value = reader.read_number("maximum value")?;
+
+    if width == 0 || height == 0 {
+        return Err("image is empty".to_string());
+    }
+    if max_value == 0 || max_value > 65535 {
+        return Err(format!("invalid maximum value {}", max_value));
+    }
+
+    let samples_count = width.checked_mul(height).and_then(|n| n.checked_mul(3)).ok_or("image is too large")?;
+    let samples = if is_binary {
+        // Exactly one whitespace separates the header from the binary data
+        reader.offset += 1;
+        let sample_size = if max_value < 256 { 1 } else { 2 };
+        let end = samples_count.checked_mul(sample_size).and_then(|size| size.checked_add(reader.offset))
+            .ok_or("image is too large")?;
+        let bytes = data.get(reader.offset..end).ok_or("pixel data is truncated")?;
+
+        if sample_size == 1 {
+            bytes.iter().map(|&byte| byte as u32).collect::<Vec<u32>>()
+        } else {
+            bytes.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]]) as u32).collect()
+        }
+    } else {
+        // Each ASCII sample takes at least two bytes, which limits the size of bogus headers
+        if samples_count > data.len() {
+            return Err("pixel data is truncated".to_string());
+        }
+        let mut samples = Vec::with_capacity(samples_count);
+        for _ in 0..samples_count {
+            samples.push(reader.read_number("sample")? as u32);
+        }
+        samples
+    };
+
+    let max_value = max_value as u32;
+    if samples.iter().any(|&sample| sample > max_value) {
+        return Err("sample exceeds the maximum value".to_string());
+    }
+
+    let to_byte = |sample: u32| (sample * 255 / max_value) as u8;
+    let pixels = samples
+        .chunks(3)
+        .map(|rgb| Color::new(to_byte(rgb[0]), to_byte(rgb[1]), to_byte(rgb[2])))
+        .collect();
+
+    return Ok(Image::new(width, height, pixels));
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize
+}
+
+impl<'a> Reader<'a> {
+    /// Read decimal number, skipping whitespace and comments before it.
+    fn read_number(&mut self, name: &str) -> Result<usize, String> {
+        loop {
+            match self.data.get(self.offset) {
+                Some(byte) if byte.is_ascii_whitespace() => self.offset += 1,
+                Some(b'#') => {
+                    while self.data.get(self.offset).is_some_and(|&byte| byte != b'\n') {
+                        self.offset += 1;
+                    }
+                },
+                _ => break
+            }
+        }
+
+        let start = self.offset;
+        while self.data.get(self.offset).is_some_and(|byte| byte.is_ascii_digit()) {
+            self.offset += 1;
+        }
+        if start == self.offset {
+            return Err(format!("missing {}", name));
+        }
+
+        // Digits are always valid text
+        let digits = std::str::from_utf8(&self.data[start..self.offset]).unwrap_or_default();
+        return digits.parse::<usize>().map_err(|_| format!("invalid {} {}", name, digits));
+    }
+}
@@ -2,6 +2,7 @@ use std::process::ExitCode;
 
 pub mod utils;
 pub mod raycaster;
+pub mod image;
 pub mod rendering;
 pub mod ui;
 pub mod behaviours;
@@ -1,26 +1,124 @@
-use crate::utils::{vector::Vector3, transform::Transform, plane_cast, EPSILON};
+use std::f32::consts::PI;
+
+use crate::utils::{vector::{Vector3, UP_VECTOR}, transform::Transform, random::Random, plane_cast};
 
 #[derive(Clone, Copy)]
 pub struct RaycastHit {
     pub position: Vector3,
     pub normal: Vector3,
     pub local_position: Vector3,
-    pub local_normal: Vector3
+    pub local_normal: Vector3,
+    /// Texture coordinates of the hit position, each face spans the [0, 1] range.
+    /// V axis points up the surface.
+    pub uv: (f32, f32),
+    /// Face of a box that was hit, `None` for other shapes.
+    pub face: Option<BoxFace>,
+    /// Derivatives of the local position by U and V coordinates, tangent to the surface.
+    pub dpdu: Vector3,
+    pub dpdv: Vector3,
+    /// How the hit changes between neighbouring pixels, filled in by the renderer.
+    pub differentials: HitDifferentials,
+    /// Distance from the ray origin to the hit position in world space.
+    pub distance: f32,
+    /// Whether the ray hit the outer side of the surface. Normals always point
+    /// outwards, so they face away from the ray on back face hits.
+    pub is_front_face: bool
 }
 
 impl RaycastHit {
-    fn as_sqr_distance(hit: &Option<RaycastHit>, origin: &Vector3) -> f32 {
-        match hit {
-            Some(h) => (h.position - origin).sqr_length(),
-            None => f32::INFINITY
+    /// Get unit tangent and bitangent in local space, perpendicular to the local normal
+    /// and following the directions of growing U and V. Used to orient normal maps and bumps.
+    pub fn get_tangent_frame(&self) -> (Vector3, Vector3) {
+        let n = self.local_normal;
+        let tangent = self.dpdu - n * Vector3::dot(&self.dpdu, &n);
+        // Tangents degenerate at the poles of the sphere
+        if tangent.sqr_length() <= f32::MIN_POSITIVE {
+            return Vector3::orthonormal_basis(&n);
+        }
+
+        let tangent = tangent.normalized();
+        let bitangent = Vector3::cross(&n, &tangent);
+        if Vector3::dot(&bitangent, &self.dpdv) < 0.0 {
+            return (tangent, -bitangent);
         }
+        return (tangent, bitangent);
+    }
+}
+
+/// Face of a box named by the local axis its normal points along.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BoxFace {
+    PositiveX, NegativeX, PositiveY, NegativeY, PositiveZ, NegativeZ
+}
+
+impl BoxFace {
+    /// All faces in the order of their indices.
+    pub const ALL: [BoxFace; 6] = [
+        BoxFace::PositiveX, BoxFace::NegativeX,
+        BoxFace::PositiveY, BoxFace::NegativeY,
+        BoxFace::PositiveZ, BoxFace::NegativeZ
+    ];
+
+    /// Get face with the normal along given axis (0 for X, 1 for Y, 2 for Z) and with given sign.
+    pub fn from_axis(axis: usize, is_positive: bool) -> BoxFace {
+        BoxFace::ALL[axis * 2 + if is_positive { 0 } else { 1 }]
+    }
+
+    /// Get index of the face, matching the order of cubemap faces.
+    pub fn get_index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        ["+X", "-X", "+Y", "-Y", "+Z", "-Z"][self.get_index()]
+    }
+}
+
+/// Change of the hit one pixel right (`dx`) and one pixel up (`dy`) on the screen,
+/// which describes the surface area covered by a pixel. All zero when not known,
+/// in which case textures are not filtered.
+#[derive(Clone, Copy, Default)]
+pub struct HitDifferentials {
+    pub dpdx: Vector3,
+    pub dpdy: Vector3,
+    pub duvdx: (f32, f32),
+    pub duvdy: (f32, f32)
+}
+
+impl HitDifferentials {
+    /// Express change of the world position in local position and UV of the hit.
+    pub fn new(hit: &RaycastHit, transform: &Transform, dpdx: &Vector3, dpdy: &Vector3) -> HitDifferentials {
+        let dpdx = transform.inverse_transform_direction(dpdx);
+        let dpdy = transform.inverse_transform_direction(dpdy);
+
+        // Tangents of all raycasters are orthogonal, so UV changes are found by projection onto them
+        let project = |dp: &Vector3| {
+            let du = Vector3::dot(dp, &hit.dpdu) / hit.dpdu.sqr_length().max(f32::MIN_POSITIVE);
+            let dv = Vector3::dot(dp, &hit.dpdv) / hit.dpdv.sqr_length().max(f32::MIN_POSITIVE);
+            (du, dv)
+        };
+
+        HitDifferentials { dpdx, dpdy, duvdx: project(&dpdx), duvdy: project(&dpdy) }
     }
 }
 
+/// Point picked uniformly on the surface of a shape.
+pub struct SurfaceSample {
+    pub position: Vector3,
+    pub normal: Vector3,
+    /// Total area of the surface, inverse of the sampling probability density.
+    pub area: f32
+}
+
 pub trait Raycaster {
+    fn get_tranform(&self) -> &Transform;
+
     fn get_mut_tranform(&mut self) -> &mut Transform;
 
     fn raycast(&self, origin: &Vector3, direction: &Vector3) -> Option<RaycastHit>;
+
+    /// Pick a random point on the surface, used to sample emissive objects like area lights.
+    fn sample_surface(&self, random: &mut Random) -> SurfaceSample;
 }
 
 pub struct BoxRaycaster {
@@ -32,80 +130,132 @@ impl BoxRaycaster {
     pub fn new(size: &Vector3) -> BoxRaycaster {
         BoxRaycaster { transform: Transform::default(), half_size: *size * 0.5 }
     }
+
+    /// Map the position on the face with given normal to UV, so that the texture
+    /// is upright and not mirrored when looking at the face from outside.
+    fn get_face_uv(&self, position: &Vector3, normal: &Vector3) -> (f32, f32) {
+        let p = *position;
+        let n = *normal;
+        let h = self.half_size;
+
+        let (u, v) = if n.x != 0.0 {
+            (n.x * p.z / h.z, p.y / h.y)
+        } else if n.y != 0.0 {
+            (p.x / h.x, n.y * p.z / h.z)
+        } else {
+            (-n.z * p.x / h.x, p.y / h.y)
+        };
+
+        return ((u + 1.0) * 0.5, (v + 1.0) * 0.5);
+    }
+
+    /// Get derivatives of the position on the face by its UV, matching `get_face_uv`.
+    fn get_face_tangents(&self, normal: &Vector3) -> (Vector3, Vector3) {
+        let n = *normal;
+        let h = self.half_size * 2.0;
+
+        if n.x != 0.0 {
+            (Vector3::new(0.0, 0.0, n.x * h.z), Vector3::new(0.0, h.y, 0.0))
+        } else if n.y != 0.0 {
+            (Vector3::new(h.x, 0.0, 0.0), Vector3::new(0.0, 0.0, n.y * h.z))
+        } else {
+            (Vector3::new(-n.z * h.x, 0.0, 0.0), Vector3::new(0.0, h.y, 0.0))
+        }
+    }
 }
 
 impl Raycaster for BoxRaycaster {
+    fn get_tranform(&self) -> &Transform { &self.transform }
+
     fn get_mut_tranform(&mut self) -> &mut Transform { &mut self.transform }
 
     fn raycast(&self, origin: &Vector3, direction: &Vector3) -> Option<RaycastHit> {
-        let mut o = self.transform.inverse_transform_position(origin);
-        let mut d = self.transform.inverse_transform_direction(direction);
+        let o: [f32; 3] = self.transform.inverse_transform_position(origin).into();
+        let d: [f32; 3] = self.transform.inverse_transform_direction(direction).into();
+        let h: [f32; 3] = self.half_size.into();
 
-        let i = (o.x < 0.0, o.y < 0.0, o.z < 0.0);
+        // Intersect the ray with slabs between each pair of opposite faces,
+        // remembering which axis the entry and exit points lie on.
+        let mut t_near = f32::NEG_INFINITY;
+        let mut t_far = f32::INFINITY;
+        let mut near_axis = 0;
+        let mut far_axis = 0;
 
-        fn invert_vector(v: &Vector3, mask: &(bool, bool, bool)) -> Vector3 {
-            let mut a = *v;
+        for axis in 0..3 {
+            let t1 = (-h[axis] - o[axis]) / d[axis];
+            let t2 = (h[axis] - o[axis]) / d[axis];
+            let (t_min, t_max) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
 
-            if mask.0 {
-                a.x *= -1.0;
-            }
-            if mask.1 {
-                a.y *= -1.0;
+            if t_min > t_near {
+                t_near = t_min;
+                near_axis = axis;
             }
-            if mask.2 {
-                a.z *= -1.0;
+            if t_max < t_far {
+                t_far = t_max;
+                far_axis = axis;
             }
+        }
 
-            return a
+        if t_far < t_near.max(0.0) {
+            return None;
         }
 
-        o = invert_vector(&o, &i);
-        d = invert_vector(&d, &i);
-
-        let try_cast_plane = |
-            plane_normal: Vector3,
-            plane_d: f32,
-        | -> Option<RaycastHit> {
-            let result = plane_cast(&plane_normal, plane_d, &o, &d);
-            
-            return result.and_then(|mut p| {
-                if 
-                    p.x.abs() <= self.half_size.x + EPSILON &&
-                    p.y.abs() <= self.half_size.y + EPSILON &&
-                    p.z.abs() <= self.half_size.z + EPSILON
-                {
-                    let n = invert_vector(&plane_normal, &i);
-                    p = invert_vector(&p, &i);
-                    Some(RaycastHit {
-                        position: self.transform.transform_position(&p),
-                        normal: self.transform.transform_direction(&n),
-                        local_position: p,
-                        local_normal: n
-                    })
-                } else {
-                    None
-                }
-            });
-        };
+        let is_front_face = t_near >= 0.0;
+        let (t, axis) = if is_front_face { (t_near, near_axis) } else { (t_far, far_axis) };
+
+        let mut n = [0.0; 3];
+        n[axis] = if is_front_face { -d[axis].signum() } else { d[axis].signum() };
+        let n = Vector3::from(n);
 
-        let results = [
-            try_cast_plane(Vector3::new(0.0, 0.0, 1.0), self.half_size.z),
-            try_cast_plane(Vector3::new(0.0, 1.0, 0.0), self.half_size.y),
-            try_cast_plane(Vector3::new(1.0, 0.0, 0.0), self.half_size.x),
-        ];
-
-        return results.iter().fold(
-            None,
-            |x, y| {
-                let d1 = RaycastHit::as_sqr_distance(&x, origin);
-                let d2 = RaycastHit::as_sqr_distance(y, origin);
-                if d1 < d2 {
-                    x
-                } else {
-                    *y
-                }
+        let p = Vector3::from(o) + Vector3::from(d) * t;
+
+        let position = self.transform.transform_position(&p);
+        let (dpdu, dpdv) = self.get_face_tangents(&n);
+
+        return Some(RaycastHit {
+            position,
+            normal: self.transform.transform_direction(&n),
+            local_position: p,
+            local_normal: n,
+            uv: self.get_face_uv(&p, &n),
+            face: Some(BoxFace::from_axis(axis, n.x + n.y + n.z > 0.0)),
+            dpdu,
+            dpdv,
+            differentials: HitDifferentials::default(),
+            distance: (position - origin).length(),
+            is_front_face
+        })
+    }
+
+    fn sample_surface(&self, random: &mut Random) -> SurfaceSample {
+        let h: [f32; 3] = self.half_size.into();
+        let face_areas = [h[1] * h[2], h[0] * h[2], h[0] * h[1]];
+        let total_area: f32 = face_areas.iter().sum();
+
+        // Pick the axis of the face with probability proportional to its area
+        let mut u = random.next_f32() * total_area;
+        let mut axis = 2;
+        for (i, area) in face_areas.iter().enumerate() {
+            if u < *area {
+                axis = i;
+                break;
             }
-        )
+            u -= area;
+        }
+
+        let sign = if random.next_f32() < 0.5 { -1.0 } else { 1.0 };
+
+        let p: [f32; 3] = std::array::from_fn(|i| {
+            if i == axis { sign * h[i] } else { (random.next_f32() * 2.0 - 1.0) * h[i] }
+        });
+        let mut n = [0.0; 3];
+        n[axis] = sign;
+
+        return SurfaceSample {
+            position: self.transform.transform_position(&Vector3::from(p)),
+            normal: self.transform.transform_direction(&Vector3::from(n)),
+            area: total_area * 8.0
+        };
     }
 }
 
@@ -121,6 +271,8 @@ impl SphereRaycaster {
 }
 
 impl Raycaster for SphereRaycaster {
+    fn get_tranform(&self) -> &Transform { &self.transform }
+
     fn get_mut_tranform(&mut self) -> &mut Transform { &mut self.transform }
 
     fn raycast(&self, origin: &Vector3, direction: &Vector3) -> Option<RaycastHit> {
@@ -128,20 +280,109 @@ impl Raycaster for SphereRaycaster {
         let d = self.transform.inverse_transform_direction(direction).normalized();
 
         let a = Vector3::dot(&-o, &d);
-        let t = a - (self.radius.powi(2) - o.sqr_length() + a.powi(2)).sqrt();
+        let k = (self.radius.powi(2) - o.sqr_length() + a.powi(2)).sqrt();
 
-        if t.is_nan() || t < 0.0 {
+        if k.is_nan() || a + k < 0.0 {
             return None;
         }
 
+        // Ray starts inside the sphere, so it can hit the back face only.
+        let is_front_face = a - k >= 0.0;
+        let t = if is_front_face { a - k } else { a + k };
+
         let p = o + d * t;
         let n = p.normalized();
 
+        let position = self.transform.transform_position(&p);
+
+        // U follows the longitude and V the latitude, tangents degenerate at the poles
+        let ring_radius = (p.x * p.x + p.z * p.z).sqrt().max(f32::MIN_POSITIVE);
+        let dpdu = Vector3::new(-p.z, 0.0, p.x) * (2.0 * PI);
+        let dpdv = Vector3::new(-p.y * p.x / ring_radius, ring_radius, -p.y * p.z / ring_radius) * PI;
+
         return Some(RaycastHit {
-            position: self.transform.transform_position(&p),
+            position,
             normal: self.transform.transform_direction(&n),
             local_position: p,
-            local_normal: n
+            local_normal: n,
+            uv: (n.z.atan2(n.x) / (2.0 * PI) + 0.5, n.y.clamp(-1.0, 1.0).asin() / PI + 0.5),
+            face: None,
+            dpdu,
+            dpdv,
+            differentials: HitDifferentials::default(),
+            distance: (position - origin).length(),
+            is_front_face
+        })
+    }
+
+    fn sample_surface(&self, random: &mut Random) -> SurfaceSample {
+        let z = 1.0 - 2.0 * random.next_f32();
+        let r = (1.0 - z * z).max(0.0).sqrt();
+        let phi = 2.0 * PI * random.next_f32();
+        let n = Vector3::new(r * phi.cos(), r * phi.sin(), z);
+
+        return SurfaceSample {
+            position: self.transform.transform_position(&(n * self.radius)),
+            normal: self.transform.transform_direction(&n),
+            area: 4.0 * PI * self.radius.powi(2)
+        };
+    }
+}
+
+/// Square plane lying in the local XZ plane and facing up.
+pub struct PlaneRaycaster {
+    transform: Transform,
+    half_size: f32
+}
+
+impl PlaneRaycaster {
+    pub fn new(size: f32) -> PlaneRaycaster {
+        PlaneRaycaster { transform: Default::default(), half_size: size * 0.5 }
+    }
+}
+
+impl Raycaster for PlaneRaycaster {
+    fn get_tranform(&self) -> &Transform { &self.transform }
+
+    fn get_mut_tranform(&mut self) -> &mut Transform { &mut self.transform }
+
+    fn raycast(&self, origin: &Vector3, direction: &Vector3) -> Option<RaycastHit> {
+        let o = self.transform.inverse_transform_position(origin);
+        let d = self.transform.inverse_transform_direction(direction);
+
+        let p = plane_cast(&UP_VECTOR, 0.0, &o, &d)?;
+        if !(p.x.abs() <= self.half_size && p.z.abs() <= self.half_size) {
+            return None;
+        }
+
+        let position = self.transform.transform_position(&p);
+
+        return Some(RaycastHit {
+            position,
+            normal: self.transform.transform_direction(&UP_VECTOR),
+            local_position: p,
+            local_normal: UP_VECTOR,
+            uv: (p.x / self.half_size * 0.5 + 0.5, p.z / self.half_size * 0.5 + 0.5),
+            face: None,
+            dpdu: Vector3::new(2.0 * self.half_size, 0.0, 0.0),
+            dpdv: Vector3::new(0.0, 0.0, 2.0 * self.half_size),
+            differentials: HitDifferentials::default(),
+            distance: (position - origin).length(),
+            is_front_face: d.y < 0.0
         })
     }
+
+    fn sample_surface(&self, random: &mut Random) -> SurfaceSample {
+        let p = Vector3::new(
+            (random.next_f32() * 2.0 - 1.0) * self.half_size,
+            0.0,
+            (random.next_f32() * 2.0 - 1.0) * self.half_size
+        );
+
+        return SurfaceSample {
+            position: self.transform.transform_position(&p),
+            normal: self.transform.transform_direction(&UP_VECTOR),
+            area: (2.0 * self.half_size).powi(2)
+        };
+    }
 }
@@ -0,0 +1,74 @@
+use std::rc::Rc;
+
+use crate::utils::{color::{Color, HdrColor}, vector::Vector3, lerp, smoothstep};
+
+use super::cubemap::Cubemap;
+
+pub const DEFAULT_SUN_ANGLE: f32 = 1.5;
+pub const DEFAULT_SUN_INTENSITY: f32 = 20.0;
+
+/// Color seen by rays, which do not hit any object.
+#[derive(Clone)]
+pub enum Background {
+    Solid(Color),
+    /// Vertical gradient from the horizon to the zenith, below the horizon
+    /// the horizon color is used.
+    Gradient { horizon_color: Color, zenith_color: Color },
+    /// Analytic sky with a sun disc in the direction the sun light comes from.
+    Sky {
+        zenith_color: Color,
+        horizon_color: Color,
+        ground_color: Color,
+        sun_color: Color,
+        /// Angular radius of the sun disc in degrees.
+        sun_angle: f32,
+        /// Brightness of the sun disc relative to the sky.
+        sun_intensity: f32
+    },
+    /// Cube of images surrounding the scene, shared with whoever loaded it.
+    Skybox(Rc<Cubemap>)
+}
+
+impl Background {
+    /// Get color seen in given direction. `sun_direction` points towards the sun
+    /// and is used only by the sky, which has no sun disc without it.
+    pub fn sample(&self, direction: &Vector3, sun_direction: Option<&Vector3>) -> HdrColor {
+        let d = direction.normalized();
+
+        match *self {
+            Background::Solid(color) => HdrColor::from(color),
+            Background::Gradient { horizon_color, zenith_color } => {
+                HdrColor::lerp(&HdrColor::from(horizon_color), &HdrColor::from(zenith_color), d.y.max(0.0))
+            },
+            Background::Sky { zenith_color, horizon_color, ground_color, sun_color, sun_angle, sun_intensity } => {
+                // Soften the horizon line a bit, so it does not alias
+                let ground_weight = smoothstep(0.02, -0.02, d.y);
+                let sky_weight = d.y.max(0.0).sqrt();
+                let mut sky = HdrColor::lerp(&HdrColor::from(horizon_color), &HdrColor::from(zenith_color), sky_weight);
+
+                if let Some(sun) = sun_direction {
+                    let sun = sun.normalized();
+                    let cos_angle = Vector3::dot(&d, &sun);
+                    let sun_light = HdrColor::from(sun_color);
+
+                    // Glow around the sun, which spreads more when the sun is low
+                    let glow_spread = lerp(4.0, 32.0, sun.y.clamp(0.0, 1.0));
+                    sky += sun_light * (0.5 * cos_angle.max(0.0).powf(glow_spread));
+
+                    let cos_sun_angle = sun_angle.to_radians().cos();
+                    let cos_edge = (sun_angle * 1.2).to_radians().cos();
+                    sky += sun_light * (sun_intensity * smoothstep(cos_edge, cos_sun_angle, cos_angle));
+                }
+
+                HdrColor::lerp(&sky, &HdrColor::from(ground_color), ground_weight)
+            },
+            Background::Skybox(ref cubemap) => cubemap.sample(&d)
+        }
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(Color::new(0, 0, 0))
+    }
+}
@@ -0,0 +1,234 @@
+use std::f32::consts::PI;
+
+use crate::utils::{color::{Color, HdrColor, BLACK_HDR_COLOR, WHITE_HDR_COLOR}, random::Random, vector::Vector3};
+
+use super::lightning::ShadingPoint;
+use super::material::Reflectance;
+
+/// Reflectance of dielectrics at normal incidence.
+const DIELECTRIC_F0: f32 = 0.04;
+/// Smallest GGX alpha, perfectly smooth surfaces make the distribution singular.
+const MIN_ALPHA: f32 = 0.002;
+
+/// Get light reflected towards the viewer off the shading point, when unit light
+/// arrives from normalized `light_direction` (pointing away from the surface).
+/// Result includes the cosine of incidence angle.
+pub fn evaluate_brdf(point: &ShadingPoint, light_direction: &Vector3) -> HdrColor {
+    let n_dot_l = Vector3::dot(&point.normal, light_direction);
+    if n_dot_l <= 0.0 {
+        return BLACK_HDR_COLOR;
+    }
+
+    let base_color = HdrColor::from(point.base_color);
+
+    match point.reflectance {
+        Reflectance::Diffuse => base_color * n_dot_l,
+        Reflectance::BlinnPhong { specular_color, shininess } => {
+            let half_vector = (*light_direction + point.view_direction).normalized();
+            let n_dot_h = Vector3::dot(&point.normal, &half_vector).max(0.0);
+
+            base_color * n_dot_l + HdrColor::from(specular_color) * n_dot_h.powf(shininess)
+        },
+        Reflectance::CookTorrance { metallic, roughness } => {
+            let n_dot_v = Vector3::dot(&point.normal, &point.view_direction);
+            if n_dot_v <= 0.0 {
+                return BLACK_HDR_COLOR;
+            }
+
+            let half_vector = (*light_direction + point.view_direction).normalized();
+            let n_dot_h = Vector3::dot(&point.normal, &half_vector).max(0.0);
+            let v_dot_h = Vector3::dot(&point.view_direction, &half_vector).max(0.0);
+
+            let alpha = get_alpha(roughness);
+            let fresnel = fresnel_schlick(&get_f0(&base_color, metallic), v_dot_h);
+            let distribution = ggx_distribution(n_dot_h, alpha);
+            let geometry = smith_geometry(n_dot_v, alpha) * smith_geometry(n_dot_l, alpha);
+
+            // Light not reflected by the microfacets enters the surface, metals absorb it
+            let diffuse = (WHITE_HDR_COLOR - fresnel) * base_color * (1.0 - metallic);
+            // Scaled by PI to stay in the same units as the Lambert term above
+            let specular = fresnel * (PI * distribution * geometry / (4.0 * n_dot_v));
+
+            diffuse * n_dot_l + specular
+        }
+    }
+}
+
+/// Pick a direction for the next bounce off the shading point, following the BRDF.
+/// Returns the direction together with the throughput weight of the path,
+/// which is BRDF times cosine divided by probability density.
+pub fn sample_brdf(point: &ShadingPoint, random: &mut Random) -> Option<(Vector3, HdrColor)> {
+    let (direction, pdf) = match point.reflectance {
+        Reflectance::CookTorrance { metallic, roughness } => {
+            let alpha = get_alpha(roughness);
+            let specular_probability = get_specular_probability(point, metallic);
+
+            let direction = if random.next_f32() < specular_probability {
+                let half_vector = sample_ggx_half_vector(&point.normal, alpha, random);
+                Vector3::reflect(&-point.view_direction, &half_vector)
+            } else {
+                random.next_cosine_direction(&point.normal)
+            };
+
+            let pdf = specular_probability * ggx_pdf(point, &direction, alpha)
+                + (1.0 - specular_probability) * cosine_pdf(&point.normal, &direction);
+            (direction, pdf)
+        },
+        Reflectance::BlinnPhong { specular_color, shininess } => {
+            // Highlight term lacks the cosine, so sampling only by the cosine would give huge weights at grazing angles
+            let specular_probability = get_highlight_probability(point, specular_color);
+
+            let direction = if random.next_f32() < specular_probability {
+                let half_vector = sample_phong_half_vector(&point.normal, shininess, random);
+                Vector3::reflect(&-point.view_direction, &half_vector)
+            } else {
+                random.next_cosine_direction(&point.normal)
+            };
+
+            let pdf = specular_probability * phong_pdf(point, &direction, shininess)
+                + (1.0 - specular_probability) * cosine_pdf(&point.normal, &direction);
+            (direction, pdf)
+        },
+        _ => {
+            let direction = random.next_cosine_direction(&point.normal);
+            (direction, cosine_pdf(&point.normal, &direction))
+        }
+    };
+
+    if pdf <= 0.0 || Vector3::dot(&point.normal, &direction) <= 0.0 {
+        return None;
+    }
+
+    // evaluate_brdf is scaled by PI, so is the density
+    return Some((direction, evaluate_brdf(point, &direction) * (1.0 / (PI * pdf))));
+}
+
+/// Get color of the mirror reflection seen at the shading point. Rough surfaces blur
+/// the reflection, so it fades out as the roughness grows.
+pub fn get_specular_reflection_weight(point: &ShadingPoint) -> HdrColor {
+    match point.reflectance {
+        Reflectance::CookTorrance { metallic, roughness } => {
+            let n_dot_v = Vector3::dot(&point.normal, &point.view_direction).max(0.0);
+            let f0 = get_f0(&HdrColor::from(point.base_color), metallic);
+
+            fresnel_schlick(&f0, n_dot_v) * (1.0 - roughness).powi(2)
+        },
+        _ => BLACK_HDR_COLOR
+    }
+}
+
+/// Get how much of the ambient light the shading point reflects diffusely and specularly.
+/// Specular part covers the blurry reflection of rough surfaces, sharp reflections
+/// get the remaining `get_specular_reflection_weight`.
+pub fn get_ambient_weights(point: &ShadingPoint) -> (HdrColor, HdrColor) {
+    let base_color = HdrColor::from(point.base_color);
+
+    match point.reflectance {
+        Reflectance::CookTorrance { metallic, roughness } => {
+            let n_dot_v = Vector3::dot(&point.normal, &point.view_direction).max(0.0);
+            let fresnel = fresnel_schlick(&get_f0(&base_color, metallic), n_dot_v);
+
+            let diffuse = (WHITE_HDR_COLOR - fresnel) * base_color * (1.0 - metallic);
+            let specular = fresnel * (1.0 - (1.0 - roughness).powi(2));
+            (diffuse, specular)
+        },
+        _ => (base_color, BLACK_HDR_COLOR)
+    }
+}
+
+#[inline]
+fn get_alpha(roughness: f32) -> f32 {
+    (roughness * roughness).max(MIN_ALPHA)
+}
+
+#[inline]
+fn get_f0(base_color: &HdrColor, metallic: f32) -> HdrColor {
+    HdrColor::lerp(&(WHITE_HDR_COLOR * DIELECTRIC_F0), base_color, metallic)
+}
+
+fn fresnel_schlick(f0: &HdrColor, cos_theta: f32) -> HdrColor {
+    let weight = (1.0 - cos_theta).clamp(0.0, 1.0).powi(5);
+    *f0 + (WHITE_HDR_COLOR - *f0) * weight
+}
+
+fn ggx_distribution(n_dot_h: f32, alpha: f32) -> f32 {
+    let alpha2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (alpha2 - 1.0) + 1.0;
+    alpha2 / (PI * d * d)
+}
+
+/// Smith-Schlick masking term for one direction.
+fn smith_geometry(n_dot_x: f32, alpha: f32) -> f32 {
+    let k = alpha / 2.0;
+    n_dot_x / (n_dot_x * (1.0 - k) + k)
+}
+
+/// Probability of sampling the specular lobe, based on how much light the surface reflects.
+fn get_specular_probability(point: &ShadingPoint, metallic: f32) -> f32 {
+    let n_dot_v = Vector3::dot(&point.normal, &point.view_direction).max(0.0);
+    let f0 = get_f0(&HdrColor::from(point.base_color), metallic);
+    let specular = fresnel_schlick(&f0, n_dot_v).max_component();
+
+    (specular + metallic * (1.0 - specular)).clamp(0.1, 0.9)
+}
+
+/// Probability of sampling the Blinn-Phong highlight, based on its share of the reflected light.
+fn get_highlight_probability(point: &ShadingPoint, specular_color: Color) -> f32 {
+    let specular = HdrColor::from(specular_color).max_component();
+    let diffuse = HdrColor::from(point.base_color).max_component();
+    if specular + diffuse <= 0.0 {
+        return 0.5;
+    }
+
+    (specular / (specular + diffuse)).clamp(0.1, 0.9)
+}
+
+fn sample_ggx_half_vector(normal: &Vector3, alpha: f32, random: &mut Random) -> Vector3 {
+    let u = random.next_f32();
+    let cos_theta = ((1.0 - u) / (1.0 + (alpha * alpha - 1.0) * u)).sqrt();
+
+    return get_lobe_direction(normal, cos_theta, random);
+}
+
+/// Sample half vector with density proportional to the power of its cosine with the normal.
+fn sample_phong_half_vector(normal: &Vector3, shininess: f32, random: &mut Random) -> Vector3 {
+    let cos_theta = random.next_f32().powf(1.0 / (shininess + 1.0));
+
+    return get_lobe_direction(normal, cos_theta, random);
+}
+
+/// Get direction at the angle to the normal with the cosine, turned around it by a random angle.
+fn get_lobe_direction(normal: &Vector3, cos_theta: f32, random: &mut Random) -> Vector3 {
+    let phi = 2.0 * PI * random.next_f32();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+
+    let (tangent, bitangent) = Vector3::orthonormal_basis(normal);
+    return tangent * (sin_theta * phi.cos()) + bitangent * (sin_theta * phi.sin()) + *normal * cos_theta;
+}
+
+fn ggx_pdf(point: &ShadingPoint, direction: &Vector3, alpha: f32) -> f32 {
+    let half_vector = (*direction + point.view_direction).normalized();
+    let n_dot_h = Vector3::dot(&point.normal, &half_vector).max(0.0);
+    let v_dot_h = Vector3::dot(&point.view_direction, &half_vector);
+    if v_dot_h <= 0.0 {
+        return 0.0;
+    }
+
+    ggx_distribution(n_dot_h, alpha) * n_dot_h / (4.0 * v_dot_h)
+}
+
+fn phong_pdf(point: &ShadingPoint, direction: &Vector3, shininess: f32) -> f32 {
+    let half_vector = (*direction + point.view_direction).normalized();
+    let n_dot_h = Vector3::dot(&point.normal, &half_vector).max(0.0);
+    let v_dot_h = Vector3::dot(&point.view_direction, &half_vector);
+    if v_dot_h <= 0.0 {
+        return 0.0;
+    }
+
+    (shininess + 1.0) / (2.0 * PI) * n_dot_h.powf(shininess) / (4.0 * v_dot_h)
+}
+
+#[inline]
+fn cosine_pdf(normal: &Vector3, direction: &Vector3) -> f32 {
+    Vector3::dot(normal, direction).max(0.0) / PI
+}
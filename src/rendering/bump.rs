@@ -0,0 +1,155 @@
+use crate::raycaster::RaycastHit;
+use crate::utils::{smoothstep, vector::Vector3, color::{Color, HdrColor}, noise::PerlinNoise};
+
+use super::material::{Material, Reflectance, MIN_PATTERN_SCALE};
+use super::texture::Texture;
+
+/// Step of the finite differences used to find slopes of height functions, in local units.
+const HEIGHT_DELTA: f32 = 0.001;
+
+/// Cosine of the largest allowed angle between perturbed and geometric normals.
+const MIN_NORMAL_COS: f32 = 0.1;
+
+/// Bricks per unit of UV along U and V, scaled by the bump scale.
+const BRICKS_COUNT: (f32, f32) = (4.0, 8.0);
+/// Half width of the mortar between bricks, width of the brick bevels and depth of the mortar
+/// at full strength, in brick heights.
+const MORTAR_HALF_WIDTH: f32 = 0.06;
+const BRICK_BEVEL_WIDTH: f32 = 0.12;
+const BRICK_DEPTH: f32 = 0.03;
+
+/// Radius and depth at full strength of the dimples, relative to the distance between them.
+const DIMPLE_RADIUS: f32 = 0.45;
+const DIMPLE_DEPTH: f32 = 0.15;
+
+/// Amplitude of the noise relief at full strength, relative to the noise scale.
+const NOISE_DEPTH: f32 = 0.1;
+
+/// Source of the small scale relief of a surface.
+#[derive(Clone)]
+pub enum BumpPattern {
+    /// Tangent space normal map sampled by UV, with X along U and Y along V.
+    NormalMap(Texture),
+    /// Rows of offset bricks separated by mortar grooves, laid out in UV.
+    Bricks,
+    /// Round dents scattered over the surface in local space.
+    Dimples(PerlinNoise),
+    /// Rough surface made of fractal noise in local space.
+    Noise(PerlinNoise)
+}
+
+/// Material adding relief to another material by perturbing its normals, which changes the
+/// lighting but not the silhouette.
+pub struct BumpMaterial {
+    pub material: Box<dyn Material>,
+    pub pattern: BumpPattern,
+    /// Size of the pattern, in UV units for bricks and normal maps or local units otherwise.
+    pub scale: f32,
+    /// Steepness of the relief, zero keeps the surface flat.
+    pub strength: f32
+}
+
+impl BumpMaterial {
+    /// Get height of the relief at the local position and UV of the hit surface, in local units.
+    fn compute_height(&self, hit: &RaycastHit, position: &Vector3, uv: (f32, f32)) -> f32 {
+        let scale = self.scale.max(MIN_PATTERN_SCALE);
+
+        match &self.pattern {
+            BumpPattern::NormalMap(_) => 0.0,
+            BumpPattern::Bricks => {
+                let brick_height = scale / BRICKS_COUNT.1 * hit.dpdv.length();
+
+                let x = uv.0 / scale * BRICKS_COUNT.0;
+                let y = uv.1 / scale * BRICKS_COUNT.1;
+                // Every other row is shifted by half of a brick
+                let x = x + 0.5 * y.floor().rem_euclid(2.0);
+
+                // Distance to the closest brick edge in brick heights
+                let fx = x - x.floor();
+                let fy = y - y.floor();
+                let aspect = BRICKS_COUNT.1 / BRICKS_COUNT.0;
+                let distance = (fx.min(1.0 - fx) * aspect).min(fy.min(1.0 - fy));
+
+                let height = smoothstep(MORTAR_HALF_WIDTH, MORTAR_HALF_WIDTH + BRICK_BEVEL_WIDTH, distance);
+                height * BRICK_DEPTH * brick_height * self.strength
+            },
+            BumpPattern::Dimples(noise) => {
+                let distance = noise.cellular(&(*position * (1.0 / scale))) / DIMPLE_RADIUS;
+                -(1.0 - distance * distance).max(0.0).powi(2) * DIMPLE_DEPTH * scale * self.strength
+            },
+            BumpPattern::Noise(noise) => {
+                noise.fbm(&(*position * (1.0 / scale)), 4) * NOISE_DEPTH * scale * self.strength
+            }
+        }
+    }
+
+    /// Get perturbed normal in the tangent space, where Z is the surface normal.
+    fn compute_tangent_normal(&self, hit: &RaycastHit, tangent: &Vector3, bitangent: &Vector3) -> Vector3 {
+        if let BumpPattern::NormalMap(texture) = &self.pattern {
+            let k = 1.0 / self.scale.max(MIN_PATTERN_SCALE);
+            let d = &hit.differentials;
+            let color = texture.sample_with_derivatives(
+                hit.uv.0 * k,
+                hit.uv.1 * k,
+                (d.duvdx.0 * k, d.duvdx.1 * k),
+                (d.duvdy.0 * k, d.duvdy.1 * k)
+            );
+
+            // Channels store the normal components mapped from [-1, 1] to [0, 1]
+            let c = HdrColor::from(color);
+            return Vector3::new((c.r * 2.0 - 1.0) * self.strength, (c.g * 2.0 - 1.0) * self.strength, c.b * 2.0 - 1.0);
+        }
+
+        // Slopes of the height along the tangents, moving along them changes UV as well
+        let slope = |direction: &Vector3| {
+            let offset = *direction * HEIGHT_DELTA;
+            let du = Vector3::dot(&offset, &hit.dpdu) / hit.dpdu.sqr_length().max(f32::MIN_POSITIVE);
+            let dv = Vector3::dot(&offset, &hit.dpdv) / hit.dpdv.sqr_length().max(f32::MIN_POSITIVE);
+
+            let forward = self.compute_height(hit, &(hit.local_position + offset), (hit.uv.0 + du, hit.uv.1 + dv));
+            let backward = self.compute_height(hit, &(hit.local_position - offset), (hit.uv.0 - du, hit.uv.1 - dv));
+            (forward - backward) / (2.0 * HEIGHT_DELTA)
+        };
+
+        // Normal of the height field tilts against its slopes
+        return Vector3::new(-slope(tangent), -slope(bitangent), 1.0);
+    }
+}
+
+impl Material for BumpMaterial {
+    fn compute_surface_color(&self, hit: &RaycastHit) -> Color {
+        self.material.compute_surface_color(hit)
+    }
+
+    fn get_reflectivity(&self) -> f32 { self.material.get_reflectivity() }
+
+    fn get_reflectance(&self) -> Reflectance { self.material.get_reflectance() }
+
+    fn get_refraction_index(&self) -> Option<f32> { self.material.get_refraction_index() }
+
+    fn get_emission(&self) -> HdrColor { self.material.get_emission() }
+
+    fn get_hit_material(&self, hit: &RaycastHit) -> Option<&dyn Material> {
+        self.material.get_hit_material(hit)
+    }
+
+    fn is_opaque(&self) -> bool { self.material.is_opaque() }
+
+    fn compute_opacity(&self, hit: &RaycastHit) -> f32 {
+        self.material.compute_opacity(hit)
+    }
+
+    fn compute_normal(&self, hit: &RaycastHit) -> Option<Vector3> {
+        let (tangent, bitangent) = hit.get_tangent_frame();
+        let n = self.compute_tangent_normal(hit, &tangent, &bitangent);
+
+        let normal = (tangent * n.x + bitangent * n.y + hit.local_normal * n.z).normalized();
+
+        // Keep the normal on the outer side of the surface, so rays leaving it do not go inside
+        let cos = Vector3::dot(&normal, &hit.local_normal);
+        if cos < MIN_NORMAL_COS {
+            return Some((normal + hit.local_normal * (MIN_NORMAL_COS - cos)).normalized());
+        }
+        return Some(normal);
+    }
+}
@@ -2,6 +2,50 @@ use std::cell::RefCell;
 
 use crate::utils::{transform::Transform, vector::Vector3, matrix::Matrix3};
 
+/// Change of a ray between neighbouring pixels, one pixel right (`dx`) and one pixel up (`dy`).
+/// Directions are differentials of normalized ray directions.
+#[derive(Clone, Copy, Default)]
+pub struct RayDifferentials {
+    pub origin_dx: Vector3,
+    pub origin_dy: Vector3,
+    pub direction_dx: Vector3,
+    pub direction_dy: Vector3
+}
+
+impl RayDifferentials {
+    /// Move the differentials along the ray with normalized `direction` to a surface with given
+    /// `normal` at `distance`, so that origin differentials become differentials of the hit position.
+    pub fn transfer(&self, direction: &Vector3, distance: f32, normal: &Vector3) -> RayDifferentials {
+        let cos = Vector3::dot(direction, normal);
+        // Offset rays travel a different distance to reach the plane tangent to the surface
+        let transfer = |origin_d: &Vector3, direction_d: &Vector3| {
+            let p = *origin_d + *direction_d * distance;
+            let dt = -Vector3::dot(&p, normal) / if cos.abs() > f32::EPSILON { cos } else { f32::EPSILON };
+            p + *direction * dt
+        };
+
+        RayDifferentials {
+            origin_dx: transfer(&self.origin_dx, &self.direction_dx),
+            origin_dy: transfer(&self.origin_dy, &self.direction_dy),
+            direction_dx: self.direction_dx,
+            direction_dy: self.direction_dy
+        }
+    }
+
+    /// Get differentials of the ray reflected off a surface with given normal,
+    /// assuming the surface is locally flat.
+    pub fn reflect(&self, normal: &Vector3) -> RayDifferentials {
+        let reflect = |d: &Vector3| *d - *normal * (2.0 * Vector3::dot(d, normal));
+
+        RayDifferentials {
+            origin_dx: self.origin_dx,
+            origin_dy: self.origin_dy,
+            direction_dx: reflect(&self.direction_dx),
+            direction_dy: reflect(&self.direction_dy)
+        }
+    }
+}
+
 pub struct Camera {
     transform: Transform,
     ray_matrix: RefCell<Matrix3>,
@@ -11,6 +55,10 @@ pub struct Camera {
 }
 
 impl Camera {
+    pub fn get_transform(&self) -> &Transform {
+        &self.transform
+    }
+
     pub fn get_mut_transform(&mut self) -> &mut Transform {
         &mut self.transform
     }
@@ -51,6 +99,22 @@ impl Camera {
 
         return (self.transform.get_position(), self.transform.transform_direction(&local_dir));
     }
+
+    /// Get differentials of the ray emitted from given pixel coordinates, where `pixel_width`
+    /// and `pixel_height` are the size of a pixel in the same coordinates.
+    pub fn get_ray_differentials(&self, x: f32, y: f32, pixel_width: f32, pixel_height: f32) -> RayDifferentials {
+        let (_, direction) = self.get_ray_origin_direction(x, y);
+        let (_, direction_x) = self.get_ray_origin_direction(x + pixel_width, y);
+        let (_, direction_y) = self.get_ray_origin_direction(x, y + pixel_height);
+        let direction = direction.normalized();
+
+        // All rays start at the camera position
+        return RayDifferentials {
+            direction_dx: direction_x.normalized() - direction,
+            direction_dy: direction_y.normalized() - direction,
+            ..Default::default()
+        };
+    }
 }
 
 impl Default for Camera {
@@ -0,0 +1,145 @@
+use crate::image::{Image, load_hdr_image};
+use crate::utils::{color::HdrColor, vector::Vector3};
+
+/// Order of the cube faces, the same as in OpenGL.
+pub const CUBEMAP_FACE_NAMES: [&str; 6] = ["+X", "-X", "+Y", "-Y", "+Z", "-Z"];
+
+/// Six square images on the faces of a cube around the scene, looked up by direction.
+pub struct Cubemap {
+    faces: [Image<HdrColor>; 6],
+    size: usize
+}
+
+impl Cubemap {
+    /// Create cubemap from square faces of the same size, ordered as in `CUBEMAP_FACE_NAMES`.
+    pub fn new(faces: [Image<HdrColor>; 6]) -> Result<Cubemap, String> {
+        let size = faces[0].get_width();
+        if size == 0 {
+            return Err("cubemap faces are empty".to_string());
+        }
+
+        for (face, name) in faces.iter().zip(CUBEMAP_FACE_NAMES) {
+            if face.get_width() != size || face.get_height() != size {
+                return Err(format!("face {} is not a square of size {}", name, size));
+            }
+        }
+
+        return Ok(Cubemap { faces, size });
+    }
+
+    pub fn load_faces(paths: &[String; 6]) -> Result<Cubemap, String> {
+        let faces = [
+            load_hdr_image(&paths[0])?,
+            load_hdr_image(&paths[1])?,
+            load_hdr_image(&paths[2])?,
+            load_hdr_image(&paths[3])?,
+            load_hdr_image(&paths[4])?,
+            load_hdr_image(&paths[5])?
+        ];
+        Cubemap::new(faces)
+    }
+
+    pub fn load_cross(path: &str) -> Result<Cubemap, String> {
+        Cubemap::from_cross(&load_hdr_image(path)?)
+    }
+
+    /// Cut faces out of a horizontal (4x3 faces) or vertical (3x4 faces) cross layout.
+    /// In the vertical cross, the -Z face below -Y is upside down.
+    pub fn from_cross(image: &Image<HdrColor>) -> Result<Cubemap, String> {
+        let width = image.get_width();
+        let height = image.get_height();
+
+        // Cells of the faces in the cross, in the order of `CUBEMAP_FACE_NAMES`
+        let (size, cells, is_vertical) = if width * 3 == height * 4 {
+            (width / 4, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (3, 1)], false)
+        } else if width * 4 == height * 3 {
+            (width / 3, [(2, 1), (0, 1), (1, 0), (1, 2), (1, 1), (1, 3)], true)
+        } else {
+            return Err(format!("{}x{} image is not a 4:3 or 3:4 cross layout", width, height));
+        };
+
+        let cut_face = |index: usize| {
+            let (cell_x, cell_y) = cells[index];
+            let is_rotated = is_vertical && index == 5;
+
+            let mut pixels = Vec::with_capacity(size * size);
+            for y in 0..size {
+                for x in 0..size {
+                    let (x, y) = if is_rotated { (size - 1 - x, size - 1 - y) } else { (x, y) };
+                    pixels.push(image.get_pixel(cell_x * size + x, cell_y * size + y));
+                }
+            }
+            Image::new(size, size, pixels)
+        };
+
+        Cubemap::new([cut_face(0), cut_face(1), cut_face(2), cut_face(3), cut_face(4), cut_face(5)])
+    }
+
+    /// Get color in given direction, filtered bilinearly. Texels across face edges
+    /// are taken from the neighbouring faces, so there are no visible seams.
+    pub fn sample(&self, direction: &Vector3) -> HdrColor {
+        let (face, u, v) = direction_to_face_uv(direction);
+
+        let x = u * self.size as f32 - 0.5;
+        let y = v * self.size as f32 - 0.5;
+        let x0 = x.floor() as i32;
+        let y0 = y.floor() as i32;
+        let tx = x - x0 as f32;
+        let ty = y - y0 as f32;
+
+        let top = HdrColor::lerp(&self.fetch(face, x0, y0), &self.fetch(face, x0 + 1, y0), tx);
+        let bottom = HdrColor::lerp(&self.fetch(face, x0, y0 + 1), &self.fetch(face, x0 + 1, y0 + 1), tx);
+
+        return HdrColor::lerp(&top, &bottom, ty);
+    }
+
+    /// Get texel of the face, which may lie outside of it by one texel.
+    fn fetch(&self, face: usize, x: i32, y: i32) -> HdrColor {
+        let size = self.size as i32;
+        if (0..size).contains(&x) && (0..size).contains(&y) {
+            return self.faces[face].get_pixel(x as usize, y as usize);
+        }
+
+        // Project the texel center, extended past the edge, onto the neighbouring face
+        let u = (x as f32 + 0.5) / self.size as f32;
+        let v = (y as f32 + 0.5) / self.size as f32;
+        let (face, u, v) = direction_to_face_uv(&face_uv_to_direction(face, u, v));
+
+        let x = ((u * self.size as f32) as usize).min(self.size - 1);
+        let y = ((v * self.size as f32) as usize).min(self.size - 1);
+        return self.faces[face].get_pixel(x, y);
+    }
+}
+
+/// Get face index and texture coordinates in [0, 1] range, with `v` going down.
+pub fn direction_to_face_uv(direction: &Vector3) -> (usize, f32, f32) {
+    let d = direction;
+    let abs = d.abs();
+
+    let (face, sc, tc, ma) = if abs.x >= abs.y && abs.x >= abs.z {
+        if d.x > 0.0 { (0, -d.z, -d.y, abs.x) } else { (1, d.z, -d.y, abs.x) }
+    } else if abs.y >= abs.z {
+        if d.y > 0.0 { (2, d.x, d.z, abs.y) } else { (3, d.x, -d.z, abs.y) }
+    } else if d.z > 0.0 {
+        (4, d.x, -d.y, abs.z)
+    } else {
+        (5, -d.x, -d.y, abs.z)
+    };
+
+    return (face, (sc / ma + 1.0) * 0.5, (tc / ma + 1.0) * 0.5);
+}
+
+/// Inverse of `direction_to_face_uv`, resulting direction is not normalized.
+pub fn face_uv_to_direction(face: usize, u: f32, v: f32) -> Vector3 {
+    let sc = u * 2.0 - 1.0;
+    let tc = v * 2.0 - 1.0;
+
+    match face {
+        0 => Vector3::new(1.0, -tc, -sc),
+        1 => Vector3::new(-1.0, -tc, sc),
+        2 => Vector3::new(sc, 1.0, tc),
+        3 => Vector3::new(sc, -1.0, -tc),
+        4 => Vector3::new(sc, -tc, 1.0),
+        _ => Vector3::new(-sc, -tc, -1.0)
+    }
+}
@@ -0,0 +1,124 @@
+use std::f32::consts::PI;
+
+use crate::image::{Image, load_hdr_image};
+use crate::utils::{color::{HdrColor, BLACK_HDR_COLOR}, vector::Vector3};
+
+/// Count of the second order spherical harmonics coefficients.
+const SH_COEFFICIENTS_COUNT: usize = 9;
+
+/// Equirectangular image of the light arriving from every direction, used both as
+/// the background and as a light source. Diffuse irradiance is precomputed as
+/// second order spherical harmonics, so lighting with it costs only a few multiplications.
+pub struct EnvironmentMap {
+    image: Image<HdrColor>,
+    intensity: f32,
+    irradiance_sh: [HdrColor; SH_COEFFICIENTS_COUNT]
+}
+
+impl EnvironmentMap {
+    pub fn new(image: Image<HdrColor>) -> EnvironmentMap {
+        let irradiance_sh = EnvironmentMap::project_to_sh(&image);
+        EnvironmentMap { image, intensity: 1.0, irradiance_sh }
+    }
+
+    pub fn load(path: &str) -> Result<EnvironmentMap, String> {
+        Ok(EnvironmentMap::new(load_hdr_image(path)?))
+    }
+
+    pub fn get_intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    pub fn set_intensity(&mut self, value: f32) {
+        self.intensity = value.max(0.0);
+    }
+
+    /// Get light arriving from given direction, filtered bilinearly.
+    pub fn sample(&self, direction: &Vector3) -> HdrColor {
+        let d = direction.normalized();
+        let u = d.z.atan2(d.x) / (2.0 * PI) + 0.5;
+        let v = d.y.clamp(-1.0, 1.0).acos() / PI;
+
+        let width = self.image.get_width();
+        let height = self.image.get_height();
+
+        let x = u * width as f32 - 0.5;
+        let y = (v * height as f32 - 0.5).clamp(0.0, (height - 1) as f32);
+        let tx = x - x.floor();
+        let ty = y - y.floor();
+
+        // Wrap around horizontally, so there is no seam behind the viewer
+        let x0 = (x.floor() as i32).rem_euclid(width as i32) as usize;
+        let x1 = (x0 + 1) % width;
+        let y0 = y.floor() as usize;
+        let y1 = (y0 + 1).min(height - 1);
+
+        let top = HdrColor::lerp(&self.image.get_pixel(x0, y0), &self.image.get_pixel(x1, y0), tx);
+        let bottom = HdrColor::lerp(&self.image.get_pixel(x0, y1), &self.image.get_pixel(x1, y1), tx);
+
+        return HdrColor::lerp(&top, &bottom, ty) * self.intensity;
+    }
+
+    /// Get cosine weighted average of the light arriving at a surface with given normal.
+    /// Lambertian surface with white color reflects exactly this light.
+    pub fn compute_irradiance(&self, normal: &Vector3) -> HdrColor {
+        // Convolution with the clamped cosine scales each band, divided by PI
+        // to get the average instead of the integral
+        const BAND_SCALES: [f32; 3] = [1.0, 2.0 / 3.0, 1.0 / 4.0];
+
+        let basis = evaluate_sh_basis(&normal.normalized());
+        let mut irradiance = BLACK_HDR_COLOR;
+        for (i, coefficient) in self.irradiance_sh.iter().enumerate() {
+            let band = if i == 0 { 0 } else if i < 4 { 1 } else { 2 };
+            irradiance += *coefficient * (basis[i] * BAND_SCALES[band]);
+        }
+
+        return HdrColor::new(
+            irradiance.r.max(0.0),
+            irradiance.g.max(0.0),
+            irradiance.b.max(0.0)
+        ) * self.intensity;
+    }
+
+    fn project_to_sh(image: &Image<HdrColor>) -> [HdrColor; SH_COEFFICIENTS_COUNT] {
+        let width = image.get_width();
+        let height = image.get_height();
+        let mut coefficients = [BLACK_HDR_COLOR; SH_COEFFICIENTS_COUNT];
+
+        // Normalize pixel solid angles, so that they sum up to the whole sphere exactly
+        let rows_weight: f32 = (0..height).map(|y| ((y as f32 + 0.5) / height as f32 * PI).sin()).sum();
+        let pixel_solid_angle = 4.0 * PI / (rows_weight * width as f32);
+
+        for y in 0..height {
+            let theta = (y as f32 + 0.5) / height as f32 * PI;
+            // Rows near the poles cover smaller solid angle
+            let solid_angle = pixel_solid_angle * theta.sin();
+
+            for x in 0..width {
+                let phi = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+                let direction = Vector3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+
+                let color = image.get_pixel(x, y) * solid_angle;
+                for (coefficient, basis) in coefficients.iter_mut().zip(evaluate_sh_basis(&direction)) {
+                    *coefficient += color * basis;
+                }
+            }
+        }
+
+        return coefficients;
+    }
+}
+
+fn evaluate_sh_basis(d: &Vector3) -> [f32; SH_COEFFICIENTS_COUNT] {
+    [
+        0.282095,
+        0.488603 * d.y,
+        0.488603 * d.z,
+        0.488603 * d.x,
+        1.092548 * d.x * d.y,
+        1.092548 * d.y * d.z,
+        0.315392 * (3.0 * d.z * d.z - 1.0),
+        1.092548 * d.x * d.z,
+        0.546274 * (d.x * d.x - d.y * d.y)
+    ]
+}
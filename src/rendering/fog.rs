@@ -0,0 +1,74 @@
+use crate::utils::vector::Vector3;
+
+pub const DEFAULT_FOG_DENSITY: f32 = 0.15;
+pub const DEFAULT_HEIGHT_FALLOFF: f32 = 1.0;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum FogFalloff {
+    /// Fog grows linearly from none at `start` to full at `end` distance.
+    Linear { start: f32, end: f32 },
+    Exponential,
+    ExponentialSquared
+}
+
+/// Fog which fades surfaces to the background color with distance from the camera.
+#[derive(Clone, Copy)]
+pub struct Fog {
+    pub falloff: FogFalloff,
+    /// Density of the exponential fogs, per unit of distance.
+    pub density: f32,
+    /// When set, density decreases exponentially above `height` at the given rate
+    /// and increases below it.
+    pub height_falloff: Option<f32>,
+    pub height: f32
+}
+
+impl Fog {
+    /// Get how much of the color seen along the ray is replaced by the fog, in [0, 1] range.
+    pub fn compute_fog_amount(&self, origin: &Vector3, direction: &Vector3, distance: f32) -> f32 {
+        let distance = match self.height_falloff {
+            Some(height_falloff) => self.compute_height_distance(origin, direction, distance, height_falloff),
+            None => distance
+        };
+
+        let amount = match self.falloff {
+            FogFalloff::Linear { start, end } => {
+                if end <= start {
+                    if distance >= start { 1.0 } else { 0.0 }
+                } else {
+                    (distance - start) / (end - start)
+                }
+            },
+            FogFalloff::Exponential => 1.0 - (-self.density * distance).exp(),
+            FogFalloff::ExponentialSquared => 1.0 - (-(self.density * distance).powi(2)).exp()
+        };
+
+        return amount.clamp(0.0, 1.0);
+    }
+
+    /// Integrate relative density of the height fog along the ray, which gives
+    /// the distance through a uniform fog with the same effect.
+    fn compute_height_distance(&self, origin: &Vector3, direction: &Vector3, distance: f32, height_falloff: f32) -> f32 {
+        let d = direction.normalized();
+        let origin_density = (-height_falloff * (origin.y - self.height)).exp();
+
+        // Horizontal rays stay at the same density
+        let k = height_falloff * d.y;
+        if k.abs() < 1e-4 {
+            return origin_density * distance;
+        }
+
+        return origin_density * (1.0 - (-k * distance).exp()) / k;
+    }
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Fog {
+            falloff: FogFalloff::Exponential,
+            density: DEFAULT_FOG_DENSITY,
+            height_falloff: None,
+            height: 0.0
+        }
+    }
+}
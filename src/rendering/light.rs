@@ -0,0 +1,219 @@
+use std::f32::consts::PI;
+
+use crate::utils::{color::{Color, HdrColor}, vector::{Vector3, UP_VECTOR}, random::Random};
+
+/// Distance to the light below which points get no light from it, since the direction
+/// to the light is undefined and the falloff grows to infinity.
+const MIN_LIGHT_DISTANCE: f32 = 1e-4;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum LightType {
+    Directional, Point, Spot,
+    /// Rectangle facing along the light direction.
+    Rect,
+    Sphere
+}
+
+/// Light source. Not every field is used by every light type.
+#[derive(Clone, Copy)]
+pub struct Light {
+    pub light_type: LightType,
+    pub color: Color,
+    pub intensity: f32,
+    /// Position of point and spot lights.
+    pub position: Vector3,
+    /// Direction in which directional and spot lights shine.
+    pub direction: Vector3,
+    /// Half angle (in degrees) of the spot light cone with full intensity.
+    pub inner_angle: f32,
+    /// Half angle (in degrees) of the spot light cone, at which intensity fades out.
+    pub outer_angle: f32,
+    /// Size of the rect light.
+    pub width: f32,
+    pub height: f32,
+    /// Radius of the sphere light.
+    pub radius: f32
+}
+
+/// Light arriving at some point from a light source.
+pub struct LightSample {
+    /// Normalized direction from the point towards the light.
+    pub direction: Vector3,
+    pub distance: f32,
+    pub color: HdrColor
+}
+
+impl Light {
+    pub fn new_directional(direction: &Vector3, color: &Color, intensity: f32) -> Light {
+        Light {
+            light_type: LightType::Directional,
+            color: *color,
+            intensity,
+            direction: *direction,
+            ..Default::default()
+        }
+    }
+
+    pub fn new_point(position: &Vector3, color: &Color, intensity: f32) -> Light {
+        Light {
+            light_type: LightType::Point,
+            color: *color,
+            intensity,
+            position: *position,
+            ..Default::default()
+        }
+    }
+
+    pub fn new_spot(
+        position: &Vector3,
+        direction: &Vector3,
+        inner_angle: f32,
+        outer_angle: f32,
+        color: &Color,
+        intensity: f32
+    ) -> Light {
+        Light {
+            light_type: LightType::Spot,
+            color: *color,
+            intensity,
+            position: *position,
+            direction: *direction,
+            inner_angle,
+            outer_angle,
+            ..Default::default()
+        }
+    }
+
+    pub fn new_rect(
+        position: &Vector3,
+        direction: &Vector3,
+        width: f32,
+        height: f32,
+        color: &Color,
+        intensity: f32
+    ) -> Light {
+        Light {
+            light_type: LightType::Rect,
+            color: *color,
+            intensity,
+            position: *position,
+            direction: *direction,
+            width,
+            height,
+            ..Default::default()
+        }
+    }
+
+    pub fn new_sphere(position: &Vector3, radius: f32, color: &Color, intensity: f32) -> Light {
+        Light {
+            light_type: LightType::Sphere,
+            color: *color,
+            intensity,
+            position: *position,
+            radius,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the light has a surface, which casts soft shadows and should be sampled
+    /// with multiple rays.
+    pub fn is_area_light(&self) -> bool {
+        self.light_type == LightType::Rect || self.light_type == LightType::Sphere
+    }
+
+    /// Get light arriving at given point. Returns `None` if the point is outside of the light.
+    /// Area lights are treated as if all light was emitted from their center.
+    pub fn illuminate(&self, position: &Vector3) -> Option<LightSample> {
+        if self.light_type == LightType::Directional {
+            return Some(LightSample {
+                direction: -self.direction.normalized(),
+                distance: f32::INFINITY,
+                color: HdrColor::from(self.color) * self.intensity
+            });
+        }
+
+        return self.illuminate_from(&self.position, position);
+    }
+
+    /// Get light arriving at given point from a random point on the surface of an area light.
+    /// Other light types always return the same sample as `illuminate`.
+    pub fn sample(&self, position: &Vector3, random: &mut Random) -> Option<LightSample> {
+        let light_position = match self.light_type {
+            LightType::Rect => {
+                let (tangent, bitangent) = Vector3::orthonormal_basis(&self.direction.normalized());
+                self.position
+                    + tangent * ((random.next_f32() - 0.5) * self.width)
+                    + bitangent * ((random.next_f32() - 0.5) * self.height)
+            },
+            LightType::Sphere => {
+                // Only the disc facing the point is visible from it
+                let (tangent, bitangent) = Vector3::orthonormal_basis(&(*position - self.position).normalized());
+                let r = self.radius * random.next_f32().sqrt();
+                let phi = 2.0 * PI * random.next_f32();
+                self.position + tangent * (r * phi.cos()) + bitangent * (r * phi.sin())
+            },
+            _ => return self.illuminate(position)
+        };
+
+        return self.illuminate_from(&light_position, position);
+    }
+
+    fn illuminate_from(&self, light_position: &Vector3, position: &Vector3) -> Option<LightSample> {
+        let offset = *light_position - *position;
+        let distance = offset.length();
+        if distance < MIN_LIGHT_DISTANCE {
+            return None;
+        }
+        let direction = offset * (1.0 / distance);
+
+        // Inverse square falloff
+        let mut attenuation = 1.0 / distance.powi(2);
+
+        match self.light_type {
+            LightType::Spot => {
+                let cos_angle = -Vector3::dot(&direction, &self.direction.normalized());
+                let cos_inner = self.inner_angle.to_radians().cos();
+                let cos_outer = self.outer_angle.to_radians().cos();
+
+                if cos_angle <= cos_outer {
+                    return None;
+                }
+
+                let t = ((cos_angle - cos_outer) / (cos_inner - cos_outer).max(f32::EPSILON)).min(1.0);
+                attenuation *= t * t * (3.0 - 2.0 * t);
+            },
+            LightType::Rect => {
+                // Rect emits only from its front side, less so at grazing angles
+                let cos_angle = -Vector3::dot(&direction, &self.direction.normalized());
+                if cos_angle <= 0.0 {
+                    return None;
+                }
+                attenuation *= cos_angle;
+            },
+            _ => {}
+        }
+
+        return Some(LightSample {
+            direction,
+            distance,
+            color: HdrColor::from(self.color) * (self.intensity * attenuation)
+        });
+    }
+}
+
+impl Default for Light {
+    fn default() -> Self {
+        Self {
+            light_type: LightType::Directional,
+            color: Color::new(255, 255, 255),
+            intensity: 1.0,
+            position: Vector3::new(0.0, 1.0, 0.0),
+            direction: -UP_VECTOR,
+            inner_angle: 20.0,
+            outer_angle: 30.0,
+            width: 1.0,
+            height: 1.0,
+            radius: 0.25
+        }
+    }
+}
@@ -1,17 +1,114 @@
-use crate::utils::{color::Color, vector::Vector3};
+use std::cell::RefCell;
+use std::f32::consts::PI;
+
+use crate::utils::{color::{Color, HdrColor, BLACK_HDR_COLOR, WHITE_HDR_COLOR}, vector::Vector3, random::Random};
+
+use super::brdf::{evaluate_brdf, get_ambient_weights};
+use super::light::{Light, LightSample};
+use super::material::Reflectance;
+use super::scene::Scene;
+
+pub const DEFAULT_SHADOW_BIAS: f32 = 0.005;
+pub const DEFAULT_AO_RADIUS: f32 = 0.5;
+pub const DEFAULT_AO_SAMPLES_COUNT: u32 = 8;
+pub const DEFAULT_AREA_LIGHT_SAMPLES_COUNT: u32 = 16;
+pub const DEFAULT_TOON_BANDS_COUNT: u32 = 3;
+pub const DEFAULT_RIM_WIDTH: f32 = 0.3;
+
+/// Point on a surface being lit.
+pub struct ShadingPoint {
+    pub position: Vector3,
+    pub normal: Vector3,
+    /// Normalized direction from the point towards the viewer.
+    pub view_direction: Vector3,
+    pub base_color: Color,
+    pub reflectance: Reflectance
+}
 
 pub trait Lightning {
-    fn apply_light(&self, base_color: Color, position: &Vector3, normal: &Vector3) -> Color;
+    fn apply_light(&self, point: &ShadingPoint, scene: &Scene) -> Color;
+
+    /// Get light reflected towards the viewer, which arrives at the point straight
+    /// from light sources. Used by the path tracer, which gathers ambient light
+    /// from bounced rays instead.
+    fn compute_direct_light(&self, point: &ShadingPoint, scene: &Scene) -> HdrColor;
+
+    fn get_lights(&self) -> &[Light] { &[] }
+}
+
+/// Ray traced ambient occlusion, which darkens ambient light
+/// in creases and near other objects.
+pub struct AmbientOcclusion {
+    /// Max distance at which objects occlude the point.
+    pub radius: f32,
+    pub samples_count: u32,
+    /// Output only the occlusion term instead of lit color.
+    pub is_debug_view: bool,
+    random: RefCell<Random>
+}
+
+impl AmbientOcclusion {
+    pub fn new(radius: f32, samples_count: u32) -> AmbientOcclusion {
+        AmbientOcclusion {
+            radius,
+            samples_count,
+            is_debug_view: false,
+            random: RefCell::default()
+        }
+    }
+
+    /// Get fraction of the hemisphere above the point not blocked by the scene,
+    /// weighted by the cosine of angle with the normal.
+    pub fn compute_visibility(&self, position: &Vector3, normal: &Vector3, scene: &Scene) -> f32 {
+        if self.samples_count == 0 {
+            return 1.0;
+        }
+
+        let mut random = self.random.borrow_mut();
+        let origin = *position + *normal * DEFAULT_SHADOW_BIAS;
+
+        let occluded_count = (0..self.samples_count)
+            .filter(|_| {
+                let direction = random.next_cosine_direction(normal);
+                scene.is_occluded(&origin, &direction, self.radius)
+            })
+            .count();
+
+        return 1.0 - (occluded_count as f32) / (self.samples_count as f32);
+    }
+
+    fn get_debug_color(visibility: f32) -> Color {
+        let v = (visibility * 255.0) as u8;
+        Color::new(v, v, v)
+    }
+}
+
+/// Get light reflected towards the viewer, which arrives evenly from all directions.
+/// It comes from the scene environment map if there is one, otherwise the constant
+/// `ambient_color` is used.
+fn compute_ambient_light(point: &ShadingPoint, ambient_color: &Color, scene: &Scene) -> HdrColor {
+    let (diffuse_weight, specular_weight) = get_ambient_weights(point);
+
+    match scene.get_environment() {
+        Some(environment) => {
+            let reflected_direction = Vector3::reflect(&-point.view_direction, &point.normal);
+            diffuse_weight * environment.compute_irradiance(&point.normal)
+                + specular_weight * environment.compute_irradiance(&reflected_direction)
+        },
+        None => (diffuse_weight + specular_weight) * HdrColor::from(*ambient_color)
+    }
 }
 
 pub struct UnlitLightning {
-    ambient_color: Color
+    ambient_color: Color,
+    ambient_occlusion: Option<AmbientOcclusion>
 }
 
 impl UnlitLightning {
     pub fn new(ambient_color: &Color) -> UnlitLightning {
         UnlitLightning {
-            ambient_color: *ambient_color
+            ambient_color: *ambient_color,
+            ambient_occlusion: None
         }
     }
 
@@ -22,46 +119,259 @@ impl UnlitLightning {
     pub fn set_ambient_color(&mut self, color: &Color) {
         self.ambient_color = *color;
     }
+
+    pub fn get_ambient_occlusion(&self) -> Option<&AmbientOcclusion> {
+        self.ambient_occlusion.as_ref()
+    }
+
+    pub fn set_ambient_occlusion(&mut self, ambient_occlusion: Option<AmbientOcclusion>) {
+        self.ambient_occlusion = ambient_occlusion;
+    }
 }
 
 impl Lightning for UnlitLightning {
-    fn apply_light(&self, base_color: Color, _position: &Vector3, _normal: &Vector3) -> Color {
-        base_color.tint(&self.ambient_color)
+    fn apply_light(&self, point: &ShadingPoint, scene: &Scene) -> Color {
+        let visibility = match &self.ambient_occlusion {
+            Some(ao) => {
+                let visibility = ao.compute_visibility(&point.position, &point.normal, scene);
+                if ao.is_debug_view {
+                    return AmbientOcclusion::get_debug_color(visibility);
+                }
+                visibility
+            },
+            None => 1.0
+        };
+
+        Color::from(compute_ambient_light(point, &self.ambient_color, scene) * visibility)
+    }
+
+    fn compute_direct_light(&self, point: &ShadingPoint, _scene: &Scene) -> HdrColor {
+        HdrColor::from(point.base_color) * HdrColor::from(self.ambient_color)
     }
 }
 
+/// Lambertian diffuse lightning from a list of light sources on top of the ambient light.
 pub struct DiffuseDirectLightning {
-    direction: Vector3,
-    normalized_dir: Vector3,
-    color: Color,
+    lights: Vec<Light>,
     ambient_color: Color,
+    cast_shadows: bool,
+    shadow_bias: f32,
+    area_light_samples_count: u32,
+    ambient_occlusion: Option<AmbientOcclusion>,
+    random: RefCell<Random>
 }
 
 impl DiffuseDirectLightning {
-    pub fn new(direction: &Vector3, color: &Color, ambient_color: &Color) -> DiffuseDirectLightning {
+    pub fn new(lights: Vec<Light>, ambient_color: &Color) -> DiffuseDirectLightning {
         DiffuseDirectLightning {
-            direction: *direction,
-            normalized_dir: -direction.normalized(),
-            color: *color,
-            ambient_color: *ambient_color
+            lights,
+            ambient_color: *ambient_color,
+            cast_shadows: true,
+            shadow_bias: DEFAULT_SHADOW_BIAS,
+            area_light_samples_count: DEFAULT_AREA_LIGHT_SAMPLES_COUNT,
+            ambient_occlusion: None,
+            random: RefCell::default()
         }
     }
 
-    pub fn get_direction(&self) -> &Vector3 {
-        &self.direction
+    pub fn get_mut_lights(&mut self) -> &mut Vec<Light> {
+        &mut self.lights
     }
 
-    pub fn set_direction(&mut self, direction: &Vector3) {
-        self.direction = *direction;
-        self.normalized_dir = -direction.normalized();
+    pub fn get_ambient_color(&self) -> &Color {
+        &self.ambient_color
+    }
+
+    pub fn set_ambient_color(&mut self, color: &Color) {
+        self.ambient_color = *color;
+    }
+
+    pub fn get_cast_shadows(&self) -> bool {
+        self.cast_shadows
+    }
+
+    pub fn set_cast_shadows(&mut self, value: bool) {
+        self.cast_shadows = value;
+    }
+
+    pub fn get_shadow_bias(&self) -> f32 {
+        self.shadow_bias
+    }
+
+    /// Set the distance by which shadow rays are pushed off the surface
+    /// along its normal to avoid self-shadowing artifacts.
+    pub fn set_shadow_bias(&mut self, value: f32) {
+        self.shadow_bias = value;
+    }
+
+    pub fn get_area_light_samples_count(&self) -> u32 {
+        self.area_light_samples_count
+    }
+
+    /// Set the number of shadow rays cast towards each area light per shading point.
+    /// More samples give smoother penumbras.
+    pub fn set_area_light_samples_count(&mut self, value: u32) {
+        self.area_light_samples_count = value.max(1);
     }
 
-    pub fn get_color(&self) -> &Color {
-        &self.color
+    pub fn get_ambient_occlusion(&self) -> Option<&AmbientOcclusion> {
+        self.ambient_occlusion.as_ref()
     }
 
-    pub fn set_color(&mut self, color: &Color) {
-        self.color = *color;
+    pub fn set_ambient_occlusion(&mut self, ambient_occlusion: Option<AmbientOcclusion>) {
+        self.ambient_occlusion = ambient_occlusion;
+    }
+
+    fn is_shadowed(&self, position: &Vector3, normal: &Vector3, sample: &LightSample, scene: &Scene) -> bool {
+        let origin = *position + *normal * self.shadow_bias;
+        scene.is_occluded(&origin, &sample.direction, sample.distance)
+    }
+
+    fn compute_sample_light(&self, point: &ShadingPoint, sample: &LightSample, scene: &Scene) -> HdrColor {
+        if Vector3::dot(&sample.direction, &point.normal) <= 0.0 {
+            return BLACK_HDR_COLOR;
+        }
+        if self.cast_shadows && self.is_shadowed(&point.position, &point.normal, sample, scene) {
+            return BLACK_HDR_COLOR;
+        }
+
+        return sample.color * evaluate_brdf(point, &sample.direction);
+    }
+
+    /// Get light reflected towards the viewer, which arrives from emissive objects
+    /// in the scene. Their surfaces are sampled the same way as area lights.
+    fn compute_emitted_light(&self, point: &ShadingPoint, scene: &Scene) -> HdrColor {
+        let mut random = self.random.borrow_mut();
+        let mut reflected_light = BLACK_HDR_COLOR;
+
+        for object in scene.get_emissive_objects() {
+            let emission = object.material.get_emission();
+
+            let mut light_sum = BLACK_HDR_COLOR;
+            for _ in 0..self.area_light_samples_count {
+                let surface = object.raycaster.sample_surface(&mut random);
+
+                let offset = surface.position - point.position;
+                let distance = offset.length();
+                let direction = offset * (1.0 / distance);
+
+                // Convex emitters never face points on their own surface, so those get skipped here
+                let cos_light = -Vector3::dot(&direction, &surface.normal);
+                if cos_light <= 0.0 {
+                    continue;
+                }
+
+                if Vector3::dot(&direction, &point.normal) <= 0.0 {
+                    continue;
+                }
+
+                // Shadow rays start at the emitter, rays grazing its surface
+                // from the other end would hit the emitter itself
+                if self.cast_shadows {
+                    let origin = surface.position + surface.normal * self.shadow_bias;
+                    let shadow_offset = point.position + point.normal * self.shadow_bias - origin;
+                    let shadow_distance = shadow_offset.length();
+
+                    if scene.is_occluded(&origin, &(shadow_offset * (1.0 / shadow_distance)), shadow_distance) {
+                        continue;
+                    }
+                }
+
+                let light_color = emission * (cos_light * surface.area / (PI * distance * distance));
+                light_sum += light_color * evaluate_brdf(point, &direction);
+            }
+            reflected_light += light_sum * (1.0 / self.area_light_samples_count as f32);
+        }
+
+        return reflected_light;
+    }
+}
+
+impl Lightning for DiffuseDirectLightning {
+    fn get_lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    fn apply_light(&self, point: &ShadingPoint, scene: &Scene) -> Color {
+        let visibility = match &self.ambient_occlusion {
+            Some(ao) => {
+                let visibility = ao.compute_visibility(&point.position, &point.normal, scene);
+                if ao.is_debug_view {
+                    return AmbientOcclusion::get_debug_color(visibility);
+                }
+                visibility
+            },
+            None => 1.0
+        };
+
+        let ambient_light = compute_ambient_light(point, &self.ambient_color, scene) * visibility;
+
+        let direct_light = self.compute_direct_light(point, scene) + self.compute_emitted_light(point, scene);
+
+        Color::from(ambient_light + direct_light)
+    }
+
+    fn compute_direct_light(&self, point: &ShadingPoint, scene: &Scene) -> HdrColor {
+        let mut random = self.random.borrow_mut();
+        let mut reflected_light = BLACK_HDR_COLOR;
+
+        for light in &self.lights {
+            if !light.is_area_light() {
+                if let Some(sample) = light.illuminate(&point.position) {
+                    reflected_light += self.compute_sample_light(point, &sample, scene);
+                }
+                continue;
+            }
+
+            let mut light_sum = BLACK_HDR_COLOR;
+            for _ in 0..self.area_light_samples_count {
+                if let Some(sample) = light.sample(&point.position, &mut random) {
+                    light_sum += self.compute_sample_light(point, &sample, scene);
+                }
+            }
+            reflected_light += light_sum * (1.0 / self.area_light_samples_count as f32);
+        }
+
+        return reflected_light;
+    }
+}
+
+/// How the toon lightning maps the cosine of the light incidence angle to light intensity.
+#[derive(Clone)]
+pub enum ToonRamp {
+    /// Split light into given number of evenly spaced bands.
+    Bands(u32),
+    /// Split light into bands with given colors, from the darkest to the brightest.
+    Colors(Vec<Color>)
+}
+
+/// Cel shading with light quantized into flat bands and a rim light outlining the silhouette.
+/// Area lights are treated as point lights with hard shadows.
+pub struct ToonLightning {
+    lights: Vec<Light>,
+    ambient_color: Color,
+    ramp: ToonRamp,
+    rim_color: Color,
+    rim_width: f32,
+    cast_shadows: bool,
+    shadow_bias: f32
+}
+
+impl ToonLightning {
+    pub fn new(lights: Vec<Light>, ambient_color: &Color) -> ToonLightning {
+        ToonLightning {
+            lights,
+            ambient_color: *ambient_color,
+            ramp: ToonRamp::Bands(DEFAULT_TOON_BANDS_COUNT),
+            rim_color: Color::new(255, 255, 255),
+            rim_width: DEFAULT_RIM_WIDTH,
+            cast_shadows: true,
+            shadow_bias: DEFAULT_SHADOW_BIAS
+        }
+    }
+
+    pub fn get_mut_lights(&mut self) -> &mut Vec<Light> {
+        &mut self.lights
     }
 
     pub fn get_ambient_color(&self) -> &Color {
@@ -71,18 +381,115 @@ impl DiffuseDirectLightning {
     pub fn set_ambient_color(&mut self, color: &Color) {
         self.ambient_color = *color;
     }
+
+    pub fn get_ramp(&self) -> &ToonRamp {
+        &self.ramp
+    }
+
+    pub fn set_ramp(&mut self, ramp: ToonRamp) {
+        self.ramp = ramp;
+    }
+
+    pub fn get_rim_color(&self) -> &Color {
+        &self.rim_color
+    }
+
+    pub fn set_rim_color(&mut self, color: &Color) {
+        self.rim_color = *color;
+    }
+
+    pub fn get_rim_width(&self) -> f32 {
+        self.rim_width
+    }
+
+    /// Set how far from the silhouette the rim light reaches. Surfaces get the rim light
+    /// where cosine of the angle between normal and view direction is below the width.
+    /// Zero disables the rim light.
+    pub fn set_rim_width(&mut self, value: f32) {
+        self.rim_width = value.clamp(0.0, 1.0);
+    }
+
+    pub fn get_cast_shadows(&self) -> bool {
+        self.cast_shadows
+    }
+
+    pub fn set_cast_shadows(&mut self, value: bool) {
+        self.cast_shadows = value;
+    }
+
+    pub fn get_shadow_bias(&self) -> f32 {
+        self.shadow_bias
+    }
+
+    pub fn set_shadow_bias(&mut self, value: f32) {
+        self.shadow_bias = value;
+    }
+
+    /// Map the cosine of the light incidence angle to the light color multiplier.
+    pub fn quantize(&self, n_dot_l: f32) -> HdrColor {
+        let n_dot_l = n_dot_l.clamp(0.0, 1.0);
+
+        match &self.ramp {
+            ToonRamp::Bands(count) => {
+                let count = (*count).max(1) as f32;
+                let intensity = (n_dot_l * count).ceil() / count;
+                WHITE_HDR_COLOR * intensity
+            },
+            ToonRamp::Colors(colors) => {
+                if colors.is_empty() {
+                    return WHITE_HDR_COLOR * n_dot_l;
+                }
+                let index = ((n_dot_l * colors.len() as f32) as usize).min(colors.len() - 1);
+                HdrColor::from(colors[index])
+            }
+        }
+    }
+
+    fn compute_rim_light(&self, point: &ShadingPoint) -> HdrColor {
+        let n_dot_v = Vector3::dot(&point.normal, &point.view_direction).max(0.0);
+        if n_dot_v > self.rim_width {
+            return BLACK_HDR_COLOR;
+        }
+
+        return HdrColor::from(self.rim_color);
+    }
 }
 
-impl Lightning for DiffuseDirectLightning {
-    fn apply_light(&self, base_color: Color, _position: &Vector3, normal: &Vector3) -> Color {
-        let k = Vector3::dot(&self.normalized_dir, normal).clamp(0.0, 1.0);
+impl Lightning for ToonLightning {
+    fn get_lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    fn apply_light(&self, point: &ShadingPoint, scene: &Scene) -> Color {
+        let ambient_light = HdrColor::from(point.base_color) * HdrColor::from(self.ambient_color);
+
+        Color::from(ambient_light + self.compute_direct_light(point, scene) + self.compute_rim_light(point))
+    }
 
-        let light_color = Color::lerp(
-            &self.ambient_color,
-            &self.color,
-            k
-        );
+    fn compute_direct_light(&self, point: &ShadingPoint, scene: &Scene) -> HdrColor {
+        let mut reflected_light = BLACK_HDR_COLOR;
+
+        for light in &self.lights {
+            let sample = match light.illuminate(&point.position) {
+                Some(v) => v,
+                None => continue
+            };
+
+            let n_dot_l = Vector3::dot(&sample.direction, &point.normal);
+            if n_dot_l <= 0.0 {
+                continue;
+            }
+            if self.cast_shadows {
+                let origin = point.position + point.normal * self.shadow_bias;
+                if scene.is_occluded(&origin, &sample.direction, sample.distance) {
+                    continue;
+                }
+            }
+
+            // Light falloff is kept, only the angle term gets quantized
+            reflected_light += sample.color * self.quantize(n_dot_l) * HdrColor::from(point.base_color);
+        }
 
-        base_color.tint(&light_color)
+        return reflected_light;
     }
 }
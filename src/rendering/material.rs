@@ -1,50 +1,167 @@
-use crate::utils::{vector::Vector3, color::Color};
+use std::f32::consts::PI;
+
+use crate::image::Image;
+use crate::raycaster::RaycastHit;
+use crate::utils::{transform::Transform, vector::Vector3, color::{Color, ColorRamp, HdrColor, BLACK_HDR_COLOR}, noise::PerlinNoise};
+
+use super::texture::Texture;
+
+/// Model of light reflection off a surface.
+#[derive(Clone, Copy)]
+pub enum Reflectance {
+    /// Lambertian diffuse reflection only.
+    Diffuse,
+    /// Diffuse reflection with Blinn-Phong specular highlights.
+    BlinnPhong { specular_color: Color, shininess: f32 },
+    /// Physically based Cook-Torrance microfacet model with GGX distribution.
+    CookTorrance { metallic: f32, roughness: f32 }
+}
 
 pub trait Material {
-    fn compute_surface_color(&self, position: &Vector3, normal: &Vector3) -> Color;
+    /// Get color of the surface at the hit, patterns use its local position or UV.
+    fn compute_surface_color(&self, hit: &RaycastHit) -> Color;
+
+    /// Get fraction of the mirror reflection blended over the shaded surface color.
+    fn get_reflectivity(&self) -> f32;
+
+    fn get_reflectance(&self) -> Reflectance { Reflectance::Diffuse }
+
+    /// Get index of refraction for transparent dielectric materials.
+    /// Opaque materials return `None`.
+    fn get_refraction_index(&self) -> Option<f32> { None }
+
+    /// Get light emitted by the surface, added regardless of the lightning.
+    fn get_emission(&self) -> HdrColor { BLACK_HDR_COLOR }
+
+    /// Get normal of the surface at the hit in local space, perturbed by its relief.
+    /// Smooth materials return `None`, keeping the geometric normal.
+    fn compute_normal(&self, _hit: &RaycastHit) -> Option<Vector3> { None }
+
+    /// Get material the hit should be shaded with instead of this one,
+    /// for materials composed of other materials.
+    fn get_hit_material(&self, _hit: &RaycastHit) -> Option<&dyn Material> { None }
+
+    /// Check whether the surface blocks all of the light everywhere, so its opacity
+    /// does not need to be computed. Materials that can not tell cheaply return false.
+    fn is_opaque(&self) -> bool { false }
+
+    /// Get how much of the light the surface at the hit blocks, in range [0, 1].
+    /// Rays pass through the rest and get blended with what is behind.
+    fn compute_opacity(&self, hit: &RaycastHit) -> f32 {
+        if self.is_opaque() {
+            return 1.0;
+        }
+        return self.compute_surface_color(hit).a as f32 / 255.0;
+    }
+
+    /// Get final color of the surface seen through the camera with given transform,
+    /// which replaces the lightning. Lit materials return `None`.
+    fn compute_view_color(&self, _hit: &RaycastHit, _camera: &Transform) -> Option<Color> { None }
 }
 
 pub struct FlatMaterial {
     pub color: Color,
+    pub reflectivity: f32,
+    pub reflectance: Reflectance
 }
 
 impl Material for FlatMaterial {
-    fn compute_surface_color(&self, _position: &Vector3, _normal: &Vector3) -> Color {
+    fn compute_surface_color(&self, _hit: &RaycastHit) -> Color {
+        self.color
+    }
+
+    fn get_reflectivity(&self) -> f32 { self.reflectivity }
+
+    fn get_reflectance(&self) -> Reflectance { self.reflectance }
+}
+
+/// Transparent material like glass or water. Reflection and refraction
+/// are weighted by the Fresnel term, `color` tints light passing inside.
+pub struct DielectricMaterial {
+    pub color: Color,
+    pub refraction_index: f32
+}
+
+impl Material for DielectricMaterial {
+    fn compute_surface_color(&self, _hit: &RaycastHit) -> Color {
+        self.color
+    }
+
+    fn get_reflectivity(&self) -> f32 { 0.0 }
+
+    fn get_refraction_index(&self) -> Option<f32> { Some(self.refraction_index) }
+}
+
+/// Material glowing with its own light, which lights other objects as well.
+pub struct EmissiveMaterial {
+    pub color: Color,
+    pub intensity: f32
+}
+
+impl Material for EmissiveMaterial {
+    fn compute_surface_color(&self, _hit: &RaycastHit) -> Color {
         self.color
     }
+
+    fn get_reflectivity(&self) -> f32 { 0.0 }
+
+    fn get_emission(&self) -> HdrColor { HdrColor::from(self.color) * self.intensity }
+}
+
+/// Physically based material in the metallic/roughness workflow.
+pub struct PbrMaterial {
+    pub base_color: Color,
+    pub metallic: f32,
+    pub roughness: f32
+}
+
+impl Material for PbrMaterial {
+    fn compute_surface_color(&self, _hit: &RaycastHit) -> Color {
+        self.base_color
+    }
+
+    fn get_reflectivity(&self) -> f32 { 0.0 }
+
+    fn get_reflectance(&self) -> Reflectance {
+        Reflectance::CookTorrance { metallic: self.metallic, roughness: self.roughness }
+    }
 }
 
 pub struct CheckerMaterial {
     pub scale: f32,
     pub first_color: Color,
-    pub second_color: Color
+    pub second_color: Color,
+    pub reflectivity: f32,
+    pub reflectance: Reflectance
 }
 
 impl CheckerMaterial {
-    fn sample_checker_texture(&self, uv_x: f32, uv_y: f32) -> f32 {
-        let x = ((uv_x / self.scale).rem_euclid(1.0) / 0.5) as u32;
-        let y = ((uv_y / self.scale).rem_euclid(1.0) / 0.5) as u32;
-        return ((x + y) % 2) as f32;
-    }
-
-    fn get_triplanar_weights(normal: &Vector3) -> Vector3 {
-        let w = normal.abs();
-        let s = w.x + w.y + w.z;
-        Vector3 {
-            x: w.x / s,
-            y: w.y / s,
-            z: w.z / s
-        }
+    /// Get average of the pattern (0 for the first color, 1 for the second one)
+    /// over a box of given size centered at the coordinates.
+    pub fn sample_checker_texture(&self, uv_x: f32, uv_y: f32, width_x: f32, width_y: f32) -> f32 {
+        // Squares are half of the scale wide
+        let k = 2.0 / self.scale.max(MIN_PATTERN_SCALE);
+        let x = filter_square_wave(uv_x * k, width_x * k);
+        let y = filter_square_wave(uv_y * k, width_y * k);
+
+        // Pattern is the exclusive or of two square waves, boxes are separable
+        // so averages of both waves can be combined the same way
+        return x + y - 2.0 * x * y;
     }
 }
 
 impl Material for CheckerMaterial {
-    fn compute_surface_color(&self, position: &Vector3, normal: &Vector3) -> Color {
-        let albedo_x = self.sample_checker_texture(position.y, position.z);
-        let albedo_y = self.sample_checker_texture(position.x, position.z);
-        let albedo_z = self.sample_checker_texture(position.x, position.y);
+    fn compute_surface_color(&self, hit: &RaycastHit) -> Color {
+        let position = &hit.local_position;
+        let dpdx = hit.differentials.dpdx.abs();
+        let dpdy = hit.differentials.dpdy.abs();
+        let width = Vector3::new(dpdx.x.max(dpdy.x), dpdx.y.max(dpdy.y), dpdx.z.max(dpdy.z));
 
-        let weights = CheckerMaterial::get_triplanar_weights(normal);
+        let albedo_x = self.sample_checker_texture(position.y, position.z, width.y, width.z);
+        let albedo_y = self.sample_checker_texture(position.x, position.z, width.x, width.z);
+        let albedo_z = self.sample_checker_texture(position.x, position.y, width.x, width.y);
+
+        let weights = get_triplanar_weights(&hit.local_normal);
 
         Color::lerp(
             &self.first_color,
@@ -52,4 +169,298 @@ impl Material for CheckerMaterial {
             albedo_x * weights.x + albedo_y * weights.y + albedo_z * weights.z
         )
     }
+
+    fn get_reflectivity(&self) -> f32 { self.reflectivity }
+
+    fn get_reflectance(&self) -> Reflectance { self.reflectance }
+
+    fn is_opaque(&self) -> bool { self.first_color.a == 255 && self.second_color.a == 255 }
+}
+
+/// Smallest size of procedural patterns, smaller scales would stretch positions to infinity.
+pub const MIN_PATTERN_SCALE: f32 = 0.001;
+
+/// Count of wood rings per unit of the noise material scale.
+const WOOD_RINGS_COUNT: f32 = 8.0;
+
+/// Colors and light direction in view space of the clay sphere of the default matcap.
+const CLAY_COLOR: HdrColor = HdrColor { r: 0.75, g: 0.68, b: 0.62 };
+const CLAY_AMBIENT: f32 = 0.25;
+const CLAY_SHININESS: f32 = 24.0;
+const CLAY_SPECULAR: f32 = 0.3;
+const CLAY_LIGHT_DIRECTION: Vector3 = Vector3 { x: -0.5, y: 0.6, z: -0.62 };
+
+/// Pattern of a procedural noise material, each maps the local position to [0, 1].
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum NoisePattern {
+    /// Single octave of gradient noise.
+    Perlin,
+    /// Fractal Brownian motion, the sum of noise octaves.
+    Fbm,
+    /// Sum of absolute noise octaves with sharp creases.
+    Turbulence,
+    /// Parallel veins along the local X axis, bent by turbulence.
+    Marble,
+    /// Concentric rings around the local Y axis, distorted by fractal noise.
+    /// Rings are several times denser than the noise features.
+    Wood
+}
+
+/// Solid texture evaluated in object space, so the pattern sticks to the object
+/// and continues through it.
+pub struct NoiseMaterial {
+    pub pattern: NoisePattern,
+    pub noise: PerlinNoise,
+    /// Colors of the pattern values from 0 to 1.
+    pub ramp: ColorRamp,
+    /// Size of the noise features and distance between marble veins, in local units.
+    pub scale: f32,
+    pub octaves_count: u32,
+    /// How strongly noise bends the marble veins and wood rings, in counts of veins or rings.
+    pub distortion: f32,
+    pub reflectivity: f32,
+    pub reflectance: Reflectance
+}
+
+impl NoiseMaterial {
+    /// Get value of the pattern at the local position, in range [0, 1].
+    pub fn compute_pattern(&self, position: &Vector3) -> f32 {
+        let p = *position * (1.0 / self.scale.max(MIN_PATTERN_SCALE));
+
+        let value = match self.pattern {
+            NoisePattern::Perlin => self.noise.sample(&p) * 0.5 + 0.5,
+            NoisePattern::Fbm => self.noise.fbm(&p, self.octaves_count) * 0.5 + 0.5,
+            NoisePattern::Turbulence => self.noise.turbulence(&p, self.octaves_count),
+            NoisePattern::Marble => {
+                let phase = p.x + self.distortion * self.noise.turbulence(&p, self.octaves_count);
+                0.5 - 0.5 * (2.0 * PI * phase).cos()
+            },
+            NoisePattern::Wood => {
+                let rings = (p.x * p.x + p.z * p.z).sqrt() * WOOD_RINGS_COUNT;
+                (rings + self.distortion * self.noise.fbm(&p, self.octaves_count)).rem_euclid(1.0)
+            }
+        };
+
+        return value.clamp(0.0, 1.0);
+    }
+}
+
+impl Material for NoiseMaterial {
+    fn compute_surface_color(&self, hit: &RaycastHit) -> Color {
+        self.ramp.sample(self.compute_pattern(&hit.local_position))
+    }
+
+    fn get_reflectivity(&self) -> f32 { self.reflectivity }
+
+    fn get_reflectance(&self) -> Reflectance { self.reflectance }
+
+    fn is_opaque(&self) -> bool { self.ramp.is_opaque() }
+}
+
+/// Material made of a separate material for each face of a box, indexed by `BoxFace`.
+/// Hits on other shapes use the first material.
+pub struct FaceMaterial {
+    pub faces: [Box<dyn Material>; 6]
+}
+
+impl FaceMaterial {
+    fn get_face_material(&self, hit: &RaycastHit) -> &dyn Material {
+        self.faces[hit.face.map_or(0, |face| face.get_index())].as_ref()
+    }
+}
+
+impl Material for FaceMaterial {
+    fn compute_surface_color(&self, hit: &RaycastHit) -> Color {
+        self.get_face_material(hit).compute_surface_color(hit)
+    }
+
+    // Renderer shades hits with the face materials, other properties are not used
+
+    fn get_reflectivity(&self) -> f32 { 0.0 }
+
+    fn get_hit_material(&self, hit: &RaycastHit) -> Option<&dyn Material> {
+        Some(self.get_face_material(hit))
+    }
+
+    fn compute_normal(&self, hit: &RaycastHit) -> Option<Vector3> {
+        self.get_face_material(hit).compute_normal(hit)
+    }
+
+    fn is_opaque(&self) -> bool { self.faces.iter().all(|face| face.is_opaque()) }
+
+    fn compute_opacity(&self, hit: &RaycastHit) -> f32 {
+        self.get_face_material(hit).compute_opacity(hit)
+    }
+}
+
+/// Material cutting holes into another material where its opacity is below the threshold.
+/// The rest is fully opaque, which keeps the edges of the holes sharp.
+pub struct CutoutMaterial {
+    pub material: Box<dyn Material>,
+    pub threshold: f32
+}
+
+impl Material for CutoutMaterial {
+    fn compute_surface_color(&self, hit: &RaycastHit) -> Color {
+        self.material.compute_surface_color(hit)
+    }
+
+    fn get_reflectivity(&self) -> f32 { self.material.get_reflectivity() }
+
+    fn get_reflectance(&self) -> Reflectance { self.material.get_reflectance() }
+
+    fn get_refraction_index(&self) -> Option<f32> { self.material.get_refraction_index() }
+
+    fn get_emission(&self) -> HdrColor { self.material.get_emission() }
+
+    fn compute_normal(&self, hit: &RaycastHit) -> Option<Vector3> {
+        self.material.compute_normal(hit)
+    }
+
+    fn get_hit_material(&self, hit: &RaycastHit) -> Option<&dyn Material> {
+        self.material.get_hit_material(hit)
+    }
+
+    fn is_opaque(&self) -> bool { self.material.is_opaque() }
+
+    fn compute_opacity(&self, hit: &RaycastHit) -> f32 {
+        if self.material.compute_opacity(hit) >= self.threshold { 1.0 } else { 0.0 }
+    }
+}
+
+/// How texture coordinates of a surface point are found.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum TextureMapping {
+    /// Coordinates provided by the raycaster for each face.
+    Uv,
+    /// Texture projected along each local axis, blended by the normal.
+    Triplanar
+}
+
+pub struct TextureMaterial {
+    pub texture: Texture,
+    pub mapping: TextureMapping,
+    /// Size of one texture repetition, in UV units or local units for the triplanar mapping.
+    pub scale: f32,
+    pub reflectivity: f32,
+    pub reflectance: Reflectance
+}
+
+impl Material for TextureMaterial {
+    fn compute_surface_color(&self, hit: &RaycastHit) -> Color {
+        let k = 1.0 / self.scale.max(MIN_PATTERN_SCALE);
+        let differentials = &hit.differentials;
+
+        match self.mapping {
+            TextureMapping::Uv => self.texture.sample_with_derivatives(
+                hit.uv.0 * k,
+                hit.uv.1 * k,
+                (differentials.duvdx.0 * k, differentials.duvdx.1 * k),
+                (differentials.duvdy.0 * k, differentials.duvdy.1 * k)
+            ),
+            TextureMapping::Triplanar => {
+                let p = hit.local_position * k;
+                let dx = differentials.dpdx * k;
+                let dy = differentials.dpdy * k;
+                let weights = get_triplanar_weights(&hit.local_normal);
+
+                let color_x = self.texture.sample_with_derivatives(p.z, p.y, (dx.z, dx.y), (dy.z, dy.y));
+                let color_y = self.texture.sample_with_derivatives(p.x, p.z, (dx.x, dx.z), (dy.x, dy.z));
+                let color_z = self.texture.sample_with_derivatives(p.x, p.y, (dx.x, dx.y), (dy.x, dy.y));
+
+                let color = HdrColor::from(color_x) * weights.x
+                    + HdrColor::from(color_y) * weights.y
+                    + HdrColor::from(color_z) * weights.z;
+                // HDR colors have no alpha, so it is blended separately
+                let alpha = color_x.a as f32 * weights.x + color_y.a as f32 * weights.y + color_z.a as f32 * weights.z;
+                Color { a: alpha.round() as u8, ..Color::from(color) }
+            }
+        }
+    }
+
+    fn get_reflectivity(&self) -> f32 { self.reflectivity }
+
+    fn get_reflectance(&self) -> Reflectance { self.reflectance }
+
+    fn is_opaque(&self) -> bool { self.texture.is_opaque() }
+}
+
+/// Material capture, which looks up the color in an image of a shaded sphere by the normal
+/// in view space. The surface looks like the sphere in the image regardless of the lightning.
+pub struct MatcapMaterial {
+    pub texture: Texture
+}
+
+impl MatcapMaterial {
+    /// Get coordinates of the sphere image point with given normal in view space.
+    /// Normals facing the camera map to the center, the rest to the disc around it.
+    pub fn get_matcap_uv(view_normal: &Vector3) -> (f32, f32) {
+        (view_normal.x * 0.5 + 0.5, view_normal.y * 0.5 + 0.5)
+    }
+
+    /// Create matcap image of a clay sphere lit from the top left.
+    pub fn create_clay_image(size: usize) -> Image<Color> {
+        let light = CLAY_LIGHT_DIRECTION.normalized();
+        let half_vector = (light + Vector3::new(0.0, 0.0, -1.0)).normalized();
+
+        let mut pixels = Vec::with_capacity(size * size);
+        for y in 0..size {
+            for x in 0..size {
+                let nx = (x as f32 + 0.5) / size as f32 * 2.0 - 1.0;
+                let ny = 1.0 - (y as f32 + 0.5) / size as f32 * 2.0;
+
+                // Corners continue the rim of the sphere, so filtering does not darken it
+                let r = (nx * nx + ny * ny).sqrt().max(1.0);
+                let (nx, ny) = (nx / r, ny / r);
+                let normal = Vector3::new(nx, ny, -(1.0 - nx * nx - ny * ny).max(0.0).sqrt());
+
+                let diffuse = Vector3::dot(&normal, &light).max(0.0);
+                let specular = Vector3::dot(&normal, &half_vector).max(0.0).powf(CLAY_SHININESS) * CLAY_SPECULAR;
+                let color = CLAY_COLOR * (CLAY_AMBIENT + diffuse * (1.0 - CLAY_AMBIENT)) + HdrColor::new(specular, specular, specular);
+                pixels.push(Color::from(color));
+            }
+        }
+
+        return Image::new(size, size, pixels);
+    }
+}
+
+impl Material for MatcapMaterial {
+    // Matcaps have no color of their own, the rest of the renderer sees the sphere center
+
+    fn compute_surface_color(&self, _hit: &RaycastHit) -> Color {
+        self.texture.sample(0.5, 0.5)
+    }
+
+    fn get_reflectivity(&self) -> f32 { 0.0 }
+
+    fn is_opaque(&self) -> bool { self.texture.is_opaque() }
+
+    fn compute_view_color(&self, hit: &RaycastHit, camera: &Transform) -> Option<Color> {
+        let view_normal = camera.inverse_transform_direction(&hit.normal).normalized();
+        let (u, v) = MatcapMaterial::get_matcap_uv(&view_normal);
+        Some(self.texture.sample(u, v))
+    }
+}
+
+/// Average the square wave, which is 0 on even and 1 on odd unit intervals,
+/// over a box of given width centered at `x`.
+fn filter_square_wave(x: f32, width: f32) -> f32 {
+    if width <= 1e-4 {
+        return x.floor().rem_euclid(2.0);
+    }
+
+    // Integral of the wave from zero, made of full periods and the odd part of the last one
+    let integral = |x: f32| (x * 0.5).floor() + (x.rem_euclid(2.0) - 1.0).max(0.0);
+    return (integral(x + width * 0.5) - integral(x - width * 0.5)) / width;
+}
+
+fn get_triplanar_weights(normal: &Vector3) -> Vector3 {
+    let w = normal.abs();
+    let s = w.x + w.y + w.z;
+    Vector3 {
+        x: w.x / s,
+        y: w.y / s,
+        z: w.z / s
+    }
 }
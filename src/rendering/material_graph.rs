@@ -0,0 +1,423 @@
+use crate::raycaster::RaycastHit;
+use crate::utils::{lerp, vector::Vector3, color::{Color, ColorRamp, HdrColor}, noise::PerlinNoise};
+
+use super::material::{Material, Reflectance, MIN_PATTERN_SCALE};
+use super::texture::Texture;
+
+/// Index of the output node, which is created with the graph and can not be removed.
+pub const OUTPUT_NODE_INDEX: usize = 0;
+
+/// Color of texture nodes without a loaded texture.
+const MISSING_TEXTURE_COLOR: Vector3 = Vector3 { x: 1.0, y: 0.0, z: 1.0 };
+
+/// Kind of value an input socket expects, which decides how it is edited while unlinked.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum SocketType {
+    Scalar,
+    Color,
+    /// Coordinates, which read the local position of the hit while unlinked.
+    Position,
+    /// Coordinates, which read the UV of the hit while unlinked.
+    Uv
+}
+
+pub struct InputSocket {
+    pub name: &'static str,
+    pub socket_type: SocketType,
+    pub default_value: [f32; 3]
+}
+
+const fn socket(name: &'static str, socket_type: SocketType, default_value: [f32; 3]) -> InputSocket {
+    InputSocket { name, socket_type, default_value }
+}
+
+const COORDINATES_INPUTS: [InputSocket; 2] = [
+    socket("Vector", SocketType::Position, [0.0; 3]),
+    socket("Scale", SocketType::Scalar, [1.0; 3])
+];
+const MIX_INPUTS: [InputSocket; 3] = [
+    socket("A", SocketType::Color, [0.0; 3]),
+    socket("B", SocketType::Color, [1.0; 3]),
+    socket("Factor", SocketType::Scalar, [0.5; 3])
+];
+const MATH_INPUTS: [InputSocket; 2] = [
+    socket("A", SocketType::Scalar, [0.0; 3]),
+    socket("B", SocketType::Scalar, [1.0; 3])
+];
+const RAMP_INPUTS: [InputSocket; 1] = [socket("Factor", SocketType::Scalar, [0.5; 3])];
+const TEXTURE_INPUTS: [InputSocket; 2] = [
+    socket("Vector", SocketType::Uv, [0.0; 3]),
+    socket("Scale", SocketType::Scalar, [1.0; 3])
+];
+const OUTPUT_INPUTS: [InputSocket; 2] = [
+    socket("Color", SocketType::Color, [1.0; 3]),
+    socket("Alpha", SocketType::Scalar, [1.0; 3])
+];
+
+/// Operation of a math node, applied to each component of its inputs.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum MathOperation {
+    Add, Subtract, Multiply, Divide, Power, Minimum, Maximum, Sine
+}
+
+impl MathOperation {
+    pub const ALL: [MathOperation; 8] = [
+        MathOperation::Add, MathOperation::Subtract, MathOperation::Multiply, MathOperation::Divide,
+        MathOperation::Power, MathOperation::Minimum, MathOperation::Maximum, MathOperation::Sine
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            MathOperation::Add => "Add",
+            MathOperation::Subtract => "Subtract",
+            MathOperation::Multiply => "Multiply",
+            MathOperation::Divide => "Divide",
+            MathOperation::Power => "Power",
+            MathOperation::Minimum => "Minimum",
+            MathOperation::Maximum => "Maximum",
+            MathOperation::Sine => "Sine"
+        }
+    }
+
+    /// Apply the operation to a pair of components. Division by zero gives zero,
+    /// sine uses the first component only.
+    pub fn apply(&self, a: f32, b: f32) -> f32 {
+        match self {
+            MathOperation::Add => a + b,
+            MathOperation::Subtract => a - b,
+            MathOperation::Multiply => a * b,
+            MathOperation::Divide => if b != 0.0 { a / b } else { 0.0 },
+            MathOperation::Power => a.abs().powf(b),
+            MathOperation::Minimum => a.min(b),
+            MathOperation::Maximum => a.max(b),
+            MathOperation::Sine => a.sin()
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum NodeKind {
+    /// Local position of the hit.
+    Position,
+    /// Local normal of the hit.
+    Normal,
+    /// Texture coordinates of the hit in X and Y.
+    Uv,
+    /// Fractal Perlin noise of the coordinates, in range [0, 1].
+    Noise { seed: u32, octaves_count: u32 },
+    /// Checker pattern of the coordinates, alternating 0 and 1 every half of the scale.
+    Checker,
+    /// Linear blend of two colors by the factor.
+    Mix,
+    Math(MathOperation),
+    /// Color of the ramp at the factor.
+    ColorRamp(ColorRamp),
+    /// Texture sampled by X and Y of the coordinates.
+    Texture { path: String, texture: Option<Texture> },
+    /// Color and alpha of the surface.
+    Output
+}
+
+impl NodeKind {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            NodeKind::Position => "Position",
+            NodeKind::Normal => "Normal",
+            NodeKind::Uv => "UV",
+            NodeKind::Noise { .. } => "Noise",
+            NodeKind::Checker => "Checker",
+            NodeKind::Mix => "Mix",
+            NodeKind::Math(operation) => operation.get_name(),
+            NodeKind::ColorRamp(_) => "Color ramp",
+            NodeKind::Texture { .. } => "Texture",
+            NodeKind::Output => "Output"
+        }
+    }
+
+    pub fn get_inputs(&self) -> &'static [InputSocket] {
+        match self {
+            NodeKind::Position | NodeKind::Normal | NodeKind::Uv => &[],
+            NodeKind::Noise { .. } | NodeKind::Checker => &COORDINATES_INPUTS,
+            NodeKind::Mix => &MIX_INPUTS,
+            NodeKind::Math(_) => &MATH_INPUTS,
+            NodeKind::ColorRamp(_) => &RAMP_INPUTS,
+            NodeKind::Texture { .. } => &TEXTURE_INPUTS,
+            NodeKind::Output => &OUTPUT_INPUTS
+        }
+    }
+
+    /// Check whether the node passes its value on, which all nodes except the output do.
+    pub fn has_output(&self) -> bool {
+        !matches!(self, NodeKind::Output)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct NodeInput {
+    /// Node whose value is read, unlinked inputs use `value` instead.
+    pub link: Option<usize>,
+    pub value: Vector3
+}
+
+#[derive(Clone)]
+pub struct GraphNode {
+    pub kind: NodeKind,
+    pub inputs: Vec<NodeInput>,
+    /// Position of the top left corner in the node editor.
+    pub position: [f32; 2]
+}
+
+/// Graph of nodes computing the surface color, which is evaluated for each hit.
+/// Each value carries three components, scalars are stored in all of them
+/// and colors in range [0, 1].
+#[derive(Clone)]
+pub struct MaterialGraph {
+    nodes: Vec<GraphNode>
+}
+
+impl MaterialGraph {
+    /// Create graph with the output node only.
+    pub fn new(output_position: [f32; 2]) -> MaterialGraph {
+        let mut graph = MaterialGraph { nodes: Vec::new() };
+        graph.add_node(NodeKind::Output, output_position);
+        graph
+    }
+
+    pub fn get_nodes(&self) -> &[GraphNode] {
+        &self.nodes
+    }
+
+    pub fn get_node(&self, index: usize) -> Option<&GraphNode> {
+        self.nodes.get(index)
+    }
+
+    pub fn get_mut_node(&mut self, index: usize) -> Option<&mut GraphNode> {
+        self.nodes.get_mut(index)
+    }
+
+    pub fn add_node(&mut self, kind: NodeKind, position: [f32; 2]) -> usize {
+        let inputs = kind.get_inputs().iter()
+            .map(|socket| NodeInput { link: None, value: Vector3::from(socket.default_value) })
+            .collect();
+
+        self.nodes.push(GraphNode { kind, inputs, position });
+        self.nodes.len() - 1
+    }
+
+    /// Remove the node together with its links. Indices of the following nodes shift down by one.
+    /// Returns `false` for the output node, which stays.
+    pub fn remove_node(&mut self, index: usize) -> bool {
+        if index == OUTPUT_NODE_INDEX || index >= self.nodes.len() {
+            return false;
+        }
+
+        self.nodes.remove(index);
+        for input in self.nodes.iter_mut().flat_map(|node| node.inputs.iter_mut()) {
+            input.link = match input.link {
+                Some(i) if i == index => None,
+                Some(i) if i > index => Some(i - 1),
+                link => link
+            };
+        }
+        return true;
+    }
+
+    /// Feed value of the `from` node to the input of the `to` node, replacing its previous link.
+    /// Links making the node depend on itself are refused.
+    pub fn link(&mut self, from: usize, to: usize, input: usize) -> Result<(), String> {
+        let source = self.nodes.get(from).ok_or("source node does not exist")?;
+        if !source.kind.has_output() {
+            return Err(format!("{} node has no value to link", source.kind.get_name()));
+        }
+        if self.nodes.get(to).is_none_or(|node| input >= node.inputs.len()) {
+            return Err("input does not exist".to_string());
+        }
+        if self.depends_on(from, to) {
+            return Err("link would make a cycle".to_string());
+        }
+
+        self.nodes[to].inputs[input].link = Some(from);
+        return Ok(());
+    }
+
+    pub fn unlink(&mut self, to: usize, input: usize) {
+        if let Some(input) = self.nodes.get_mut(to).and_then(|node| node.inputs.get_mut(input)) {
+            input.link = None;
+        }
+    }
+
+    /// Check whether value of the node is computed from value of the other node, or is the same node.
+    fn depends_on(&self, node: usize, other: usize) -> bool {
+        // Inputs of several nodes may share the source, so each node is visited once
+        let mut is_visited = vec![false; self.nodes.len()];
+        let mut stack = vec![node];
+        while let Some(index) = stack.pop() {
+            if index == other {
+                return true;
+            }
+            if is_visited[index] {
+                continue;
+            }
+            is_visited[index] = true;
+            stack.extend(self.nodes[index].inputs.iter().filter_map(|input| input.link));
+        }
+        return false;
+    }
+}
+
+/// Source of an input value of a compiled step.
+#[derive(Clone, Copy)]
+enum StepInput {
+    Step(usize),
+    Value(Vector3),
+    Position,
+    Uv
+}
+
+struct GraphStep {
+    kind: NodeKind,
+    /// Noise of the noise nodes, built once for their seed.
+    noise: Option<PerlinNoise>,
+    inputs: Vec<StepInput>
+}
+
+/// Material computing the surface color by a material graph.
+pub struct GraphMaterial {
+    /// Nodes the output depends on, ordered so that each step comes after its inputs.
+    /// The output node is the last step.
+    steps: Vec<GraphStep>,
+    /// Whether the alpha of the output is a constant of one or more.
+    is_opaque: bool,
+    pub reflectivity: f32,
+    pub reflectance: Reflectance
+}
+
+impl GraphMaterial {
+    pub fn new(graph: &MaterialGraph, reflectivity: f32, reflectance: Reflectance) -> GraphMaterial {
+        let mut steps = Vec::new();
+        let mut step_indices = vec![None; graph.nodes.len()];
+        GraphMaterial::compile_node(graph, OUTPUT_NODE_INDEX, &mut steps, &mut step_indices);
+
+        let output = steps.last().expect("Graph must have the output step");
+        let is_opaque = matches!(output.inputs[1], StepInput::Value(alpha) if to_scalar(&alpha) >= 1.0);
+
+        GraphMaterial { steps, is_opaque, reflectivity, reflectance }
+    }
+
+    fn compile_node(
+        graph: &MaterialGraph, index: usize, steps: &mut Vec<GraphStep>, step_indices: &mut [Option<usize>]
+    ) -> usize {
+        if let Some(step_index) = step_indices[index] {
+            return step_index;
+        }
+
+        let node = &graph.nodes[index];
+        let inputs = node.inputs.iter()
+            .zip(node.kind.get_inputs())
+            .map(|(input, socket)| match (input.link, socket.socket_type) {
+                (Some(link), _) => StepInput::Step(GraphMaterial::compile_node(graph, link, steps, step_indices)),
+                (None, SocketType::Position) => StepInput::Position,
+                (None, SocketType::Uv) => StepInput::Uv,
+                (None, _) => StepInput::Value(input.value)
+            })
+            .collect();
+
+        let noise = match node.kind {
+            NodeKind::Noise { seed, .. } => Some(PerlinNoise::new(seed)),
+            _ => None
+        };
+
+        steps.push(GraphStep { kind: node.kind.clone(), noise, inputs });
+        step_indices[index] = Some(steps.len() - 1);
+        return steps.len() - 1;
+    }
+
+    /// Get color and alpha of the output node at the hit.
+    pub fn evaluate(&self, hit: &RaycastHit) -> (Vector3, f32) {
+        let mut values: Vec<Vector3> = Vec::with_capacity(self.steps.len());
+
+        for step in &self.steps {
+            let input = |i: usize| resolve_input(step.inputs[i], &values, hit);
+            let scalar = |i: usize| to_scalar(&input(i));
+            // Scales divide the coordinates, so they are kept away from zero
+            let scale = |i: usize| scalar(i).max(MIN_PATTERN_SCALE);
+
+            let value = match &step.kind {
+                NodeKind::Position => hit.local_position,
+                NodeKind::Normal => hit.local_normal,
+                NodeKind::Uv => Vector3::new(hit.uv.0, hit.uv.1, 0.0),
+                NodeKind::Noise { octaves_count, .. } => {
+                    let noise = step.noise.as_ref().expect("Noise steps must have noise");
+                    let position = input(0) * (1.0 / scale(1));
+                    let value = 0.5 + 0.5 * noise.fbm(&position, *octaves_count);
+                    Vector3::new(value, value, value)
+                },
+                NodeKind::Checker => {
+                    // Squares are half of the scale wide
+                    let p = input(0) * (2.0 / scale(1));
+                    let parity = (p.x.floor() + p.y.floor() + p.z.floor()).rem_euclid(2.0);
+                    Vector3::new(parity, parity, parity)
+                },
+                NodeKind::Mix => {
+                    let (a, b, t) = (input(0), input(1), scalar(2));
+                    Vector3::new(lerp(a.x, b.x, t), lerp(a.y, b.y, t), lerp(a.z, b.z, t))
+                },
+                NodeKind::Math(operation) => {
+                    let (a, b) = (input(0), input(1));
+                    Vector3::new(operation.apply(a.x, b.x), operation.apply(a.y, b.y), operation.apply(a.z, b.z))
+                },
+                NodeKind::ColorRamp(ramp) => {
+                    let color = HdrColor::from(ramp.sample(scalar(0)));
+                    Vector3::new(color.r, color.g, color.b)
+                },
+                NodeKind::Texture { texture, .. } => match texture {
+                    Some(texture) => {
+                        let k = 1.0 / scale(1);
+                        let uv = input(0) * k;
+                        // Footprint is estimated from the surface UV, whatever coordinates the node gets
+                        let d = &hit.differentials;
+                        let color = HdrColor::from(texture.sample_with_derivatives(
+                            uv.x, uv.y, (d.duvdx.0 * k, d.duvdx.1 * k), (d.duvdy.0 * k, d.duvdy.1 * k)
+                        ));
+                        Vector3::new(color.r, color.g, color.b)
+                    },
+                    None => MISSING_TEXTURE_COLOR
+                },
+                NodeKind::Output => input(0)
+            };
+            values.push(value);
+        }
+
+        let output = self.steps.last().expect("Graph must have the output step");
+        let alpha = to_scalar(&resolve_input(output.inputs[1], &values, hit));
+        return (values[values.len() - 1], alpha);
+    }
+}
+
+fn resolve_input(input: StepInput, values: &[Vector3], hit: &RaycastHit) -> Vector3 {
+    match input {
+        StepInput::Step(index) => values[index],
+        StepInput::Value(value) => value,
+        StepInput::Position => hit.local_position,
+        StepInput::Uv => Vector3::new(hit.uv.0, hit.uv.1, 0.0)
+    }
+}
+
+/// Read the value as a scalar, colors and vectors give the average of their components.
+fn to_scalar(value: &Vector3) -> f32 {
+    (value.x + value.y + value.z) / 3.0
+}
+
+impl Material for GraphMaterial {
+    fn compute_surface_color(&self, hit: &RaycastHit) -> Color {
+        let (color, alpha) = self.evaluate(hit);
+        let color = Color::from(HdrColor::new(color.x, color.y, color.z));
+        return Color { a: (alpha.clamp(0.0, 1.0) * 255.0).round() as u8, ..color };
+    }
+
+    fn get_reflectivity(&self) -> f32 { self.reflectivity }
+
+    fn get_reflectance(&self) -> Reflectance { self.reflectance }
+
+    fn is_opaque(&self) -> bool { self.is_opaque }
+}
@@ -0,0 +1,48 @@
+use std::f32::consts::PI;
+
+pub const DEFAULT_MEDIUM_DENSITY: f32 = 0.1;
+pub const DEFAULT_ANISOTROPY: f32 = 0.6;
+pub const DEFAULT_MEDIUM_STEPS_COUNT: u32 = 32;
+
+/// Distance up to which rays, which hit nothing, are marched through the medium.
+pub const MAX_MEDIUM_DISTANCE: f32 = 20.0;
+
+/// Homogeneous participating medium filling the scene, like haze or dust. It scatters
+/// light of the light sources towards the viewer, so shadowed parts of it
+/// form visible light shafts.
+#[derive(Clone, Copy)]
+pub struct Medium {
+    /// Count of particles per unit of distance, they both scatter and block light.
+    pub density: f32,
+    /// Henyey-Greenstein asymmetry in (-1, 1) range, positive values scatter light forward,
+    /// so the shafts are brighter when looking towards the light.
+    pub anisotropy: f32,
+    /// Count of ray marching steps along each camera ray.
+    pub steps_count: u32
+}
+
+impl Medium {
+    /// Get fraction of light passing through given distance of the medium.
+    pub fn compute_transmittance(&self, distance: f32) -> f32 {
+        (-self.density * distance).exp()
+    }
+
+    /// Get probability density of scattering light by the angle with given cosine
+    /// between the light direction and the scattered direction.
+    pub fn compute_phase(&self, cos_angle: f32) -> f32 {
+        let g = self.anisotropy.clamp(-0.99, 0.99);
+        let denominator = 1.0 + g * g - 2.0 * g * cos_angle;
+
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+}
+
+impl Default for Medium {
+    fn default() -> Self {
+        Medium {
+            density: DEFAULT_MEDIUM_DENSITY,
+            anisotropy: DEFAULT_ANISOTROPY,
+            steps_count: DEFAULT_MEDIUM_STEPS_COUNT
+        }
+    }
+}
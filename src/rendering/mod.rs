@@ -1,5 +1,16 @@
 pub mod pixel_canvas;
 pub mod camera;
+pub mod texture;
 pub mod material;
+pub mod bump;
+pub mod material_graph;
+pub mod brdf;
+pub mod light;
+pub mod environment;
+pub mod cubemap;
+pub mod background;
+pub mod fog;
+pub mod medium;
 pub mod lightning;
+pub mod scene;
 pub mod renderer;
@@ -1,22 +1,66 @@
 use std::cell::{RefCell, Ref, RefMut};
+use std::f32::consts::PI;
 use std::rc::Rc;
 
 use imgui_glow_renderer::TextureMap as ImguiTextureMap;
 
-use crate::raycaster::{Raycaster, RaycastHit, BoxRaycaster};
-use crate::utils::{color::Color, vector::Vector3};
+use crate::raycaster::{Raycaster, RaycastHit, HitDifferentials, BoxRaycaster, PlaneRaycaster};
+use crate::utils::{
+    color::{Color, HdrColor, BLACK_HDR_COLOR, WHITE_HDR_COLOR},
+    vector::Vector3,
+    random::Random,
+    fresnel_schlick,
+    EPSILON
+};
 
-use super::camera::Camera;
-use super::lightning::{Lightning, DiffuseDirectLightning};
-use super::material::{Material, CheckerMaterial};
+use super::brdf::{sample_brdf, get_specular_reflection_weight};
+use super::camera::{Camera, RayDifferentials};
+use super::background::Background;
+use super::environment::EnvironmentMap;
+use super::fog::Fog;
+use super::medium::{Medium, MAX_MEDIUM_DISTANCE};
+use super::light::{Light, LightType};
+use super::lightning::{Lightning, DiffuseDirectLightning, ShadingPoint};
+use super::material::{Material, CheckerMaterial, FlatMaterial, Reflectance};
 use super::pixel_canvas::PixelCanvas;
+use super::scene::{Scene, SceneObject};
+
+const MAIN_OBJECT_INDEX: usize = 0;
+const GROUND_OBJECT_INDEX: usize = 1;
+
+pub const DEFAULT_GROUND_HEIGHT: f32 = -1.0;
+const GROUND_SIZE: f32 = 8.0;
+
+pub const DEFAULT_MAX_DEPTH: u32 = 3;
+
+/// Count of bounces after which paths start being terminated by Russian roulette.
+const RUSSIAN_ROULETTE_DEPTH: u32 = 2;
+/// Largest count of transparent surfaces a path may pass through between two bounces.
+const MAX_PASS_THROUGH_COUNT: u32 = 16;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum RenderMode {
+    /// Whitted-style ray tracing with direct lightning, reflections and refractions.
+    DirectLighting,
+    /// Monte Carlo path tracing, which accumulates one sample per pixel each frame.
+    PathTraced
+}
 
 pub struct Renderer {
     pixel_canvas: RefCell<PixelCanvas>,
     camera: Rc<RefCell<Camera>>,
-    raycaster: Box<dyn Raycaster>,
-    material: Box<dyn Material>,
-    lightning: Box<dyn Lightning>
+    scene: Scene,
+    lightning: Box<dyn Lightning>,
+    background: Background,
+    fog: Option<Fog>,
+    medium: Option<Medium>,
+    render_mode: RenderMode,
+    max_depth: u32,
+    frame_rays_count: usize,
+    random: RefCell<Random>,
+    accumulation_buffer: Vec<HdrColor>,
+    samples_count: u32,
+    last_camera_state: (Vector3, Vector3, f32, f32)
 }
 
 impl Renderer {
@@ -32,31 +76,134 @@ impl Renderer {
         self.camera.clone()
     }
 
+    pub fn get_scene(&self) -> &Scene {
+        &self.scene
+    }
+
     pub fn get_mut_raycaster(&mut self) -> &mut dyn Raycaster {
-        self.raycaster.as_mut()
+        self.reset_accumulation();
+        self.get_mut_main_object().raycaster.as_mut()
     }
 
     pub fn set_raycaster(&mut self, raycaster: Box<dyn Raycaster>) {
-        self.raycaster = raycaster;
+        self.reset_accumulation();
+        self.get_mut_main_object().raycaster = raycaster;
     }
 
     pub fn get_mut_material(&mut self) -> &mut dyn Material {
-        self.material.as_mut()
+        self.reset_accumulation();
+        self.get_mut_main_object().material.as_mut()
     }
 
     pub fn set_material(&mut self, material: Box<dyn Material>) {
-        self.material = material;
+        self.reset_accumulation();
+        self.get_mut_main_object().material = material;
+    }
+
+    pub fn get_mut_ground_plane(&mut self) -> &mut SceneObject {
+        self.reset_accumulation();
+        self.scene.get_mut_object(GROUND_OBJECT_INDEX).expect("Scene has no ground plane")
     }
 
     pub fn get_mut_lightning(&mut self) -> &mut dyn Lightning {
+        self.reset_accumulation();
         self.lightning.as_mut()
     }
 
     pub fn set_lightning(&mut self, lightning: Box<dyn Lightning>) {
+        self.reset_accumulation();
         self.lightning = lightning;
     }
 
+    pub fn get_mut_environment(&mut self) -> Option<&mut EnvironmentMap> {
+        self.reset_accumulation();
+        self.scene.get_mut_environment()
+    }
+
+    pub fn set_environment(&mut self, environment: Option<EnvironmentMap>) {
+        self.reset_accumulation();
+        self.scene.set_environment(environment);
+    }
+
+    pub fn get_background(&self) -> &Background {
+        &self.background
+    }
+
+    /// Set the color seen where rays hit nothing. Environment map takes precedence when set.
+    pub fn set_background(&mut self, background: Background) {
+        self.reset_accumulation();
+        self.background = background;
+    }
+
+    pub fn get_fog(&self) -> Option<&Fog> {
+        self.fog.as_ref()
+    }
+
+    /// Set fog applied after shading, which blends hit colors to the background by distance.
+    pub fn set_fog(&mut self, fog: Option<Fog>) {
+        self.reset_accumulation();
+        self.fog = fog;
+    }
+
+    pub fn get_medium(&self) -> Option<&Medium> {
+        self.medium.as_ref()
+    }
+
+    /// Set medium filling the scene, which scatters light along camera rays.
+    pub fn set_medium(&mut self, medium: Option<Medium>) {
+        self.reset_accumulation();
+        self.medium = medium;
+    }
+
+    pub fn get_render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+
+    pub fn set_render_mode(&mut self, value: RenderMode) {
+        self.reset_accumulation();
+        self.render_mode = value;
+    }
+
+    pub fn get_max_depth(&self) -> u32 {
+        self.max_depth
+    }
+
+    /// Set how many times a ray may bounce off reflective surfaces
+    /// (or off any surface in the path traced mode).
+    pub fn set_max_depth(&mut self, value: u32) {
+        self.reset_accumulation();
+        self.max_depth = value;
+    }
+
+    /// Get count of samples per pixel accumulated in the path traced mode.
+    pub fn get_samples_count(&self) -> u32 {
+        self.samples_count
+    }
+
+    /// Discard samples accumulated in the path traced mode. Called by every
+    /// method giving mutable access to the scene, changes of the camera and
+    /// render size are detected automatically.
+    pub fn reset_accumulation(&mut self) {
+        self.samples_count = 0;
+    }
+
+    /// Get count of all rays (including shadow rays) cast during the last frame.
+    pub fn get_frame_rays_count(&self) -> usize {
+        self.frame_rays_count
+    }
+
     pub fn render(&mut self) {
+        self.scene.reset_rays_count();
+
+        match self.render_mode {
+            RenderMode::DirectLighting => self.render_direct_lighting(),
+            RenderMode::PathTraced => self.render_path_traced()
+        };
+
+        self.frame_rays_count = self.scene.get_rays_count();
+    }
+
+    fn render_direct_lighting(&self) {
         let width = self.pixel_canvas.borrow().get_width();
         let height = self.pixel_canvas.borrow().get_height();
         let w = width as f32;
@@ -67,7 +214,9 @@ impl Renderer {
                 for x in 0..width {
                     let pixel = self.render_pixel(
                         (x as f32) / w * 2.0 - 1.0,
-                        (h - (y as f32)) / h * 2.0 - 1.0
+                        (h - (y as f32)) / h * 2.0 - 1.0,
+                        2.0 / w,
+                        2.0 / h
                     );
 
                     writer.write_pixel(x, y, pixel);
@@ -76,25 +225,445 @@ impl Renderer {
         });
     }
 
-    fn render_pixel(&self, clip_x: f32, clip_y: f32) -> Color {
-        let (p, d) = self.camera.borrow().get_ray_origin_direction(clip_x, clip_y);
+    fn render_path_traced(&mut self) {
+        let width = self.pixel_canvas.borrow().get_width();
+        let height = self.pixel_canvas.borrow().get_height();
+        let w = width as f32;
+        let h = height as f32;
+
+        let camera_state = self.get_camera_state();
+        if camera_state != self.last_camera_state || self.accumulation_buffer.len() != width * height {
+            self.last_camera_state = camera_state;
+            self.reset_accumulation();
+        }
 
-        match self.raycaster.raycast(&p, &d) {
-            Some(hit) => self.compute_solid_color(&hit),
-            None => self.compute_background_color(&d)
+        if self.samples_count == 0 {
+            self.accumulation_buffer.clear();
+            self.accumulation_buffer.resize(width * height, BLACK_HDR_COLOR);
         }
+
+        let mut buffer = std::mem::take(&mut self.accumulation_buffer);
+        let samples_count = self.samples_count + 1;
+        let k = 1.0 / (samples_count as f32);
+
+        self.pixel_canvas.borrow_mut().update_with(|mut writer| {
+            for y in 0..height {
+                for x in 0..width {
+                    // Jitter sample position inside the pixel to get antialiasing for free
+                    let (jitter_x, jitter_y) = {
+                        let mut random = self.random.borrow_mut();
+                        (random.next_f32(), random.next_f32())
+                    };
+
+                    let clip_x = (x as f32 + jitter_x) / w * 2.0 - 1.0;
+                    let clip_y = (h - (y as f32 + jitter_y)) / h * 2.0 - 1.0;
+                    let (p, d, differentials) = {
+                        let camera = self.camera.borrow();
+                        let (p, d) = camera.get_ray_origin_direction(clip_x, clip_y);
+                        (p, d, camera.get_ray_differentials(clip_x, clip_y, 2.0 / w, 2.0 / h))
+                    };
+
+                    let idx = x + y * width;
+                    buffer[idx] += self.trace_path(&p, &d, &differentials);
+
+                    writer.write_pixel(x, y, Color::from(buffer[idx] * k));
+                }
+            }
+        });
+
+        self.accumulation_buffer = buffer;
+        self.samples_count = samples_count;
     }
 
-    fn compute_solid_color(&self, hit: &RaycastHit) -> Color {
-        let base_color = self.material.compute_surface_color(&hit.local_position, &hit.local_normal);
-        return self.lightning.apply_light(base_color, &hit.position, &hit.normal)
+    fn get_camera_state(&self) -> (Vector3, Vector3, f32, f32) {
+        let camera = self.camera.borrow();
+        let transform = camera.get_transform();
+
+        return (
+            transform.get_position(),
+            transform.get_rotation(),
+            camera.get_fov(),
+            camera.get_aspect_ratio()
+        );
+    }
+
+    fn get_mut_main_object(&mut self) -> &mut SceneObject {
+        self.scene.get_mut_object(MAIN_OBJECT_INDEX).expect("Scene has no main object")
+    }
+
+    fn render_pixel(&self, clip_x: f32, clip_y: f32, pixel_width: f32, pixel_height: f32) -> Color {
+        let camera = self.camera.borrow();
+        let (p, d) = camera.get_ray_origin_direction(clip_x, clip_y);
+        let differentials = camera.get_ray_differentials(clip_x, clip_y, pixel_width, pixel_height);
+        self.trace_ray(&p, &d, &differentials, 0)
     }
 
-    fn compute_background_color(&self, _direction: &Vector3) -> Color {
-        Color::new(0, 0, 0)
+    fn trace_ray(&self, origin: &Vector3, direction: &Vector3, differentials: &RayDifferentials, depth: u32) -> Color {
+        let (color, distance) = match self.scene.raycast(origin, direction) {
+            Some((mut hit, object)) => {
+                let differentials = apply_differentials(&mut hit, object.raycaster.as_ref(), direction, differentials);
+                apply_relief(&mut hit, object);
+
+                // Surfaces are blended front to back with what is seen through them
+                let opacity = object.material.compute_opacity(&hit);
+                let color = if opacity >= 1.0 {
+                    self.compute_solid_color(&hit, get_hit_material(&hit, object), direction, &differentials, depth)
+                } else {
+                    let behind_color = self.trace_ray(
+                        &(hit.position + direction.normalized() * EPSILON), direction, &differentials, depth
+                    );
+                    if opacity <= 0.0 {
+                        behind_color
+                    } else {
+                        let surface_color = self.compute_solid_color(
+                            &hit, get_hit_material(&hit, object), direction, &differentials, depth
+                        );
+                        Color::lerp(&behind_color, &surface_color, opacity)
+                    }
+                };
+
+                let fog_amount = self.compute_fog_amount(origin, direction, hit.distance);
+                if fog_amount <= 0.0 {
+                    (color, hit.distance)
+                } else {
+                    let background_color = Color::from(self.compute_background_color(direction));
+                    (Color::lerp(&color, &background_color, fog_amount), hit.distance)
+                }
+            },
+            None => (Color::from(self.compute_background_color(direction)), MAX_MEDIUM_DISTANCE)
+        };
+
+        let medium = match &self.medium {
+            Some(medium) => medium,
+            None => return color
+        };
+
+        let (scattered_light, transmittance) = self.compute_medium_light(
+            medium, origin, direction, distance, &mut self.random.borrow_mut()
+        );
+        return Color::from(HdrColor::from(color) * transmittance + scattered_light);
+    }
+
+    fn compute_solid_color(
+        &self,
+        hit: &RaycastHit,
+        material: &dyn Material,
+        direction: &Vector3,
+        differentials: &RayDifferentials,
+        depth: u32
+    ) -> Color {
+        if let Some(color) = material.compute_view_color(hit, self.camera.borrow().get_transform()) {
+            return color;
+        }
+
+        if let Some(refraction_index) = material.get_refraction_index() {
+            return self.compute_dielectric_color(hit, material, direction, differentials, depth, refraction_index);
+        }
+
+        // Inner faces are seen through transparent surfaces
+        let n = if hit.is_front_face { hit.normal } else { -hit.normal };
+        let point = get_shading_point(hit, &n, material, direction);
+        let color = Color::from(HdrColor::from(self.lightning.apply_light(&point, &self.scene)) + material.get_emission());
+
+        let reflectivity = material.get_reflectivity();
+        let specular_weight = get_specular_reflection_weight(&point);
+        if (reflectivity <= 0.0 && specular_weight.max_component() <= 0.0) || depth >= self.max_depth {
+            return color;
+        }
+
+        let reflected_dir = Vector3::reflect(direction, &n);
+        let reflected_color = self.trace_ray(
+            &(hit.position + n * EPSILON), &reflected_dir, &differentials.reflect(&n), depth + 1
+        );
+
+        let color = Color::from(HdrColor::from(color) + HdrColor::from(reflected_color) * specular_weight);
+        return Color::lerp(&color, &reflected_color, reflectivity)
+    }
+
+    fn compute_dielectric_color(
+        &self,
+        hit: &RaycastHit,
+        material: &dyn Material,
+        direction: &Vector3,
+        differentials: &RayDifferentials,
+        depth: u32,
+        refraction_index: f32
+    ) -> Color {
+        let point = get_shading_point(hit, &hit.normal, material, direction);
+
+        if depth >= self.max_depth {
+            return self.lightning.apply_light(&point, &self.scene);
+        }
+
+        let d = direction.normalized();
+        let (n, ior_from, ior_to) = if hit.is_front_face {
+            (hit.normal, 1.0, refraction_index)
+        } else {
+            (-hit.normal, refraction_index, 1.0)
+        };
+
+        let reflected_color = self.trace_ray(
+            &(hit.position + n * EPSILON), &Vector3::reflect(&d, &n), &differentials.reflect(&n), depth + 1
+        );
+
+        let (refracted_dir, fresnel) = match refract_with_fresnel(&d, &n, ior_from, ior_to) {
+            Some(v) => v,
+            None => return reflected_color
+        };
+
+        // Bending of the differentials by refraction is ignored
+        let mut refracted_color = self.trace_ray(
+            &(hit.position - n * EPSILON), &refracted_dir, differentials, depth + 1
+        );
+        if hit.is_front_face {
+            refracted_color = refracted_color.tint(&point.base_color);
+        }
+
+        return Color::lerp(&refracted_color, &reflected_color, fresnel)
+    }
+
+    /// Trace a single random light path, returning radiance arriving along it.
+    /// Differentials are followed through specular bounces only, diffuse bounces
+    /// blur textures anyway.
+    fn trace_path(&self, origin: &Vector3, direction: &Vector3, differentials: &RayDifferentials) -> HdrColor {
+        let mut random = self.random.borrow_mut();
+
+        let mut radiance = BLACK_HDR_COLOR;
+        let mut throughput = WHITE_HDR_COLOR;
+        let mut origin = *origin;
+        let mut direction = direction.normalized();
+        let mut differentials = *differentials;
+
+        for depth in 0..=self.max_depth {
+            // Passing through transparent surfaces does not count as a bounce
+            let mut pass_through_count = 0;
+            let (hit, object) = loop {
+                let hit = self.scene.raycast(&origin, &direction);
+
+                if let Some(medium) = &self.medium {
+                    let distance = hit.as_ref().map_or(MAX_MEDIUM_DISTANCE, |(hit, _)| hit.distance);
+                    let (scattered_light, transmittance) = self.compute_medium_light(
+                        medium, &origin, &direction, distance, &mut random
+                    );
+                    radiance += throughput * scattered_light;
+                    throughput = throughput * transmittance;
+                }
+
+                let (mut hit, object) = match hit {
+                    Some(v) => v,
+                    None => {
+                        radiance += throughput * self.compute_background_color(&direction);
+                        return radiance;
+                    }
+                };
+                differentials = apply_differentials(&mut hit, object.raycaster.as_ref(), &direction, &differentials);
+                apply_relief(&mut hit, object);
+
+                // Fog scatters the background light towards the viewer and hides what is behind it
+                let fog_amount = self.compute_fog_amount(&origin, &direction, hit.distance);
+                if fog_amount > 0.0 {
+                    radiance += throughput * self.compute_background_color(&direction) * fog_amount;
+                    throughput = throughput * (1.0 - fog_amount);
+                }
+
+                // Transparent surfaces let the path through with probability of their transparency
+                if random.next_f32() >= object.material.compute_opacity(&hit) {
+                    pass_through_count += 1;
+                    if pass_through_count > MAX_PASS_THROUGH_COUNT {
+                        return radiance;
+                    }
+                    origin = hit.position + direction * EPSILON;
+                    continue;
+                }
+
+                break (hit, object);
+            };
+
+            let material = get_hit_material(&hit, object);
+            let n = if hit.is_front_face { hit.normal } else { -hit.normal };
+            let point = get_shading_point(&hit, &n, material, &direction);
+            let color = HdrColor::from(point.base_color);
+
+            // Emissive objects are not sampled as lights, so their light is gathered by hitting them
+            radiance += throughput * material.get_emission();
+
+            // Materials replacing the lightning end the path with their color
+            if let Some(color) = material.compute_view_color(&hit, self.camera.borrow().get_transform()) {
+                radiance += throughput * HdrColor::from(color);
+                break;
+            }
+
+            if let Some(refraction_index) = material.get_refraction_index() {
+                let (ior_from, ior_to) = if hit.is_front_face {
+                    (1.0, refraction_index)
+                } else {
+                    (refraction_index, 1.0)
+                };
+
+                // Choose either reflection or refraction with probability of the Fresnel term
+                match refract_with_fresnel(&direction, &n, ior_from, ior_to) {
+                    Some((refracted_dir, fresnel)) if random.next_f32() >= fresnel => {
+                        origin = hit.position - n * EPSILON;
+                        direction = refracted_dir;
+                        if hit.is_front_face {
+                            throughput *= color;
+                        }
+                    },
+                    _ => {
+                        origin = hit.position + n * EPSILON;
+                        direction = Vector3::reflect(&direction, &n);
+                        differentials = differentials.reflect(&n);
+                    }
+                };
+                continue;
+            }
+
+            origin = hit.position + n * EPSILON;
+
+            if random.next_f32() < material.get_reflectivity() {
+                direction = Vector3::reflect(&direction, &n);
+                differentials = differentials.reflect(&n);
+                continue;
+            }
+
+            // Next event estimation: sample light sources directly on each diffuse bounce
+            radiance += throughput * self.lightning.compute_direct_light(&point, &self.scene);
+
+            let (next_direction, weight) = match sample_brdf(&point, &mut random) {
+                Some(v) => v,
+                None => break
+            };
+            throughput *= weight;
+
+            if depth >= RUSSIAN_ROULETTE_DEPTH {
+                let survival_probability = throughput.max_component().clamp(0.05, 0.95);
+                if random.next_f32() >= survival_probability {
+                    break;
+                }
+                throughput = throughput * (1.0 / survival_probability);
+            }
+
+            direction = next_direction;
+            differentials = RayDifferentials::default();
+        }
+
+        return radiance;
+    }
+
+    /// Ray march the medium along the ray up to given distance, gathering light scattered
+    /// towards the ray origin. Returns the scattered light together with the transmittance
+    /// of the whole segment.
+    fn compute_medium_light(
+        &self, medium: &Medium, origin: &Vector3, direction: &Vector3, distance: f32, random: &mut Random
+    ) -> (HdrColor, f32) {
+        let d = direction.normalized();
+        let steps_count = medium.steps_count.max(1);
+        let step = distance / steps_count as f32;
+        let step_transmittance = medium.compute_transmittance(step);
+
+        // Jitter the steps, which turns banding into noise
+        let offset = random.next_f32();
+
+        let mut scattered_light = BLACK_HDR_COLOR;
+        let mut transmittance = 1.0;
+        for i in 0..steps_count {
+            let position = *origin + d * ((i as f32 + offset) * step);
+
+            for light in self.lightning.get_lights() {
+                let sample = match light.illuminate(&position) {
+                    Some(v) => v,
+                    None => continue
+                };
+                if self.scene.is_occluded(&position, &sample.direction, sample.distance) {
+                    continue;
+                }
+
+                // Light scattered within the step, scaled by PI to be in the units of surface lightning
+                let phase = medium.compute_phase(Vector3::dot(&sample.direction, &d));
+                let scattering = medium.density * step * phase * PI;
+                scattered_light += sample.color * (transmittance * scattering);
+            }
+
+            transmittance *= step_transmittance;
+        }
+
+        return (scattered_light, transmittance);
+    }
+
+    fn compute_fog_amount(&self, origin: &Vector3, direction: &Vector3, distance: f32) -> f32 {
+        match &self.fog {
+            Some(fog) => fog.compute_fog_amount(origin, direction, distance),
+            None => 0.0
+        }
+    }
+
+    fn compute_background_color(&self, direction: &Vector3) -> HdrColor {
+        if let Some(environment) = self.scene.get_environment() {
+            return environment.sample(direction);
+        }
+
+        // Sun is placed where the first directional light comes from
+        let sun_direction = self.lightning.get_lights().iter()
+            .find(|light| light.light_type == LightType::Directional)
+            .map(|light| -light.direction.normalized());
+
+        return self.background.sample(direction, sun_direction.as_ref());
     }
 }
 
+/// Fill in how the hit changes between pixels, returning the ray differentials moved to the hit.
+fn apply_differentials(
+    hit: &mut RaycastHit, raycaster: &dyn Raycaster, direction: &Vector3, differentials: &RayDifferentials
+) -> RayDifferentials {
+    let differentials = differentials.transfer(&direction.normalized(), hit.distance, &hit.normal);
+    hit.differentials = HitDifferentials::new(
+        hit, raycaster.get_tranform(), &differentials.origin_dx, &differentials.origin_dy
+    );
+    return differentials;
+}
+
+/// Replace the normal of the hit by the one perturbed by relief of the object material.
+fn apply_relief(hit: &mut RaycastHit, object: &SceneObject) {
+    if let Some(normal) = object.material.compute_normal(hit) {
+        hit.normal = object.raycaster.get_tranform().transform_direction(&normal).normalized();
+    }
+}
+
+/// Get material of the object at the hit, resolving composed materials.
+fn get_hit_material<'a>(hit: &RaycastHit, object: &'a SceneObject) -> &'a dyn Material {
+    let material = object.material.as_ref();
+    return material.get_hit_material(hit).unwrap_or(material);
+}
+
+fn get_shading_point(
+    hit: &RaycastHit, normal: &Vector3, material: &dyn Material, direction: &Vector3
+) -> ShadingPoint {
+    ShadingPoint {
+        position: hit.position,
+        normal: *normal,
+        view_direction: -direction.normalized(),
+        base_color: material.compute_surface_color(hit),
+        reflectance: material.get_reflectance()
+    }
+}
+
+/// Refract normalized `direction` at the boundary with given `normal` facing against it.
+/// Returns refracted direction and Fresnel reflectance, or `None` on total internal reflection.
+fn refract_with_fresnel(
+    direction: &Vector3, normal: &Vector3, ior_from: f32, ior_to: f32
+) -> Option<(Vector3, f32)> {
+    let refracted_dir = Vector3::refract(direction, normal, ior_from / ior_to)?;
+
+    // Fresnel term is evaluated with the angle on the less dense side of the boundary
+    let cos_theta = if ior_from > ior_to {
+        -Vector3::dot(&refracted_dir, normal)
+    } else {
+        -Vector3::dot(direction, normal)
+    };
+
+    return Some((refracted_dir, fresnel_schlick(cos_theta, ior_from, ior_to)));
+}
+
 pub fn build_renderer(
     gl: Rc<glow::Context>, texture_map: &mut dyn ImguiTextureMap, render_size: [usize; 2]
 ) -> Result<Renderer, String> {
@@ -109,13 +678,31 @@ pub fn build_renderer(
     let material = Box::new(CheckerMaterial { 
         scale: 1.0,
         first_color: Color::new(255, 255, 255),
-        second_color: Color::new(127, 127, 127)
+        second_color: Color::new(127, 127, 127),
+        reflectivity: 0.0,
+        reflectance: Reflectance::Diffuse
     });
 
+    let mut ground_raycaster = Box::new(PlaneRaycaster::new(GROUND_SIZE));
+    ground_raycaster.get_mut_tranform().set_position(&Vector3::new(0.0, DEFAULT_GROUND_HEIGHT, 0.0));
+
+    let ground_material = Box::new(FlatMaterial {
+        color: Color::new(200, 200, 200),
+        reflectivity: 0.0,
+        reflectance: Reflectance::Diffuse
+    });
+
+    let mut scene = Scene::new(Vec::new());
+    scene.add_object(SceneObject::new(raycaster, material));
+    scene.add_object(SceneObject::new(ground_raycaster, ground_material));
+
     let lightning = Box::new(
         DiffuseDirectLightning::new(
-            &Vector3::new(1.0, -1.0, 1.0),
-            &Color::new(255, 255, 255),
+            vec![Light::new_directional(
+                &Vector3::new(1.0, -1.0, 1.0),
+                &Color::new(255, 255, 255),
+                1.0
+            )],
             &Color::new(40, 40, 40)
         )
     );
@@ -129,9 +716,18 @@ pub fn build_renderer(
     let renderer = Renderer {
         pixel_canvas,
         camera: Rc::new(RefCell::new(camera)),
-        raycaster,
-        material,
-        lightning
+        scene,
+        lightning,
+        background: Background::default(),
+        fog: None,
+        medium: None,
+        render_mode: RenderMode::DirectLighting,
+        max_depth: DEFAULT_MAX_DEPTH,
+        frame_rays_count: 0,
+        random: RefCell::new(Random::default()),
+        accumulation_buffer: Vec::new(),
+        samples_count: 0,
+        last_camera_state: Default::default()
     };
 
     Ok(renderer)
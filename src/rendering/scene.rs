@@ -0,0 +1,120 @@
+use std::cell::Cell;
+
+use crate::raycaster::{Raycaster, RaycastHit};
+use crate::utils::{vector::Vector3, EPSILON};
+
+use super::environment::EnvironmentMap;
+use super::material::Material;
+
+/// Opacity from which surfaces cast shadows. Shadows are not partial,
+/// so semi-transparent surfaces block light where they are mostly opaque.
+const SHADOW_OPACITY_THRESHOLD: f32 = 0.5;
+
+pub struct SceneObject {
+    pub raycaster: Box<dyn Raycaster>,
+    pub material: Box<dyn Material>,
+    pub is_visible: bool
+}
+
+impl SceneObject {
+    pub fn new(raycaster: Box<dyn Raycaster>, material: Box<dyn Material>) -> SceneObject {
+        SceneObject { raycaster, material, is_visible: true }
+    }
+}
+
+pub struct Scene {
+    objects: Vec<SceneObject>,
+    environment: Option<EnvironmentMap>,
+    rays_count: Cell<usize>
+}
+
+impl Scene {
+    pub fn new(objects: Vec<SceneObject>) -> Scene {
+        Scene { objects, environment: None, rays_count: Cell::new(0) }
+    }
+
+    /// Get count of rays cast into the scene since the last reset.
+    pub fn get_rays_count(&self) -> usize {
+        self.rays_count.get()
+    }
+
+    pub fn reset_rays_count(&self) {
+        self.rays_count.set(0);
+    }
+
+    pub fn get_object(&self, index: usize) -> Option<&SceneObject> {
+        self.objects.get(index)
+    }
+
+    pub fn get_mut_object(&mut self, index: usize) -> Option<&mut SceneObject> {
+        self.objects.get_mut(index)
+    }
+
+    pub fn add_object(&mut self, object: SceneObject) -> usize {
+        self.objects.push(object);
+        self.objects.len() - 1
+    }
+
+    pub fn get_environment(&self) -> Option<&EnvironmentMap> {
+        self.environment.as_ref()
+    }
+
+    pub fn get_mut_environment(&mut self) -> Option<&mut EnvironmentMap> {
+        self.environment.as_mut()
+    }
+
+    /// Set the image surrounding the scene, which is seen in the background and lights objects.
+    pub fn set_environment(&mut self, environment: Option<EnvironmentMap>) {
+        self.environment = environment;
+    }
+
+    /// Get visible objects, which emit light.
+    pub fn get_emissive_objects(&self) -> impl Iterator<Item = &SceneObject> {
+        self.objects.iter().filter(|o| o.is_visible && o.material.get_emission().max_component() > 0.0)
+    }
+
+    /// Find the closest hit along the ray together with the object that was hit.
+    pub fn raycast(&self, origin: &Vector3, direction: &Vector3) -> Option<(RaycastHit, &SceneObject)> {
+        self.rays_count.set(self.rays_count.get() + 1);
+
+        let mut closest: Option<(RaycastHit, &SceneObject)> = None;
+        let mut closest_distance = f32::INFINITY;
+
+        for object in self.objects.iter().filter(|o| o.is_visible) {
+            if let Some(hit) = object.raycaster.raycast(origin, direction) {
+                if hit.distance < closest_distance {
+                    closest_distance = hit.distance;
+                    closest = Some((hit, object));
+                }
+            }
+        }
+
+        return closest;
+    }
+
+    /// Check whether any object blocks the ray closer than `max_distance`.
+    /// The ray passes through transparent parts of the surfaces.
+    pub fn is_occluded(&self, origin: &Vector3, direction: &Vector3, max_distance: f32) -> bool {
+        self.rays_count.set(self.rays_count.get() + 1);
+
+        let d = direction.normalized();
+        self.objects.iter().filter(|o| o.is_visible).any(|object| {
+            let mut origin = *origin;
+            let mut max_distance = max_distance;
+
+            while let Some(hit) = object.raycaster.raycast(&origin, &d) {
+                if hit.distance >= max_distance {
+                    return false;
+                }
+                if object.material.compute_opacity(&hit) >= SHADOW_OPACITY_THRESHOLD {
+                    return true;
+                }
+
+                // Continue behind the surface, which may be hit again from inside
+                origin = hit.position + d * EPSILON;
+                max_distance -= hit.distance + EPSILON;
+            }
+            return false;
+        })
+    }
+}
@@ -0,0 +1,189 @@
+use std::rc::Rc;
+
+use crate::image::Image;
+use crate::utils::color::Color;
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+    /// Bilinear filtering blended between two mip levels chosen by the pixel footprint.
+    Trilinear
+}
+
+/// How texture coordinates outside of the [0, 1] range are mapped back into it.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum TextureWrap {
+    Repeat, Clamp, Mirror
+}
+
+/// Image sampled by texture coordinates, which may be shared between materials.
+#[derive(Clone)]
+pub struct Texture {
+    /// Mip chain starting with the full image, each level is half the size of the previous one.
+    levels: Rc<Vec<Image<Color>>>,
+    /// Whether all pixels of the image are fully opaque.
+    is_opaque: bool,
+    pub filter: TextureFilter,
+    pub wrap: TextureWrap
+}
+
+impl Texture {
+    pub fn new(image: Image<Color>) -> Texture {
+        let mut levels = vec![image];
+        while let Some(level) = downsample(levels.last().expect("Mip chain is never empty")) {
+            levels.push(level);
+        }
+
+        let is_opaque = levels[0].get_pixels().iter().all(|pixel| pixel.a == 255);
+        Texture { levels: Rc::new(levels), is_opaque, filter: TextureFilter::Trilinear, wrap: TextureWrap::Repeat }
+    }
+
+    /// Get copy of the texture sharing its images, which is sampled with given filter and wrap mode.
+    pub fn with_sampling(&self, filter: TextureFilter, wrap: TextureWrap) -> Texture {
+        Texture { levels: self.levels.clone(), is_opaque: self.is_opaque, filter, wrap }
+    }
+
+    pub fn is_opaque(&self) -> bool { self.is_opaque }
+
+    pub fn get_image(&self) -> &Image<Color> {
+        &self.levels[0]
+    }
+
+    pub fn get_levels_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Get color at given texture coordinates, V axis points up the image.
+    /// The full size image is used, as if the texture was magnified.
+    pub fn sample(&self, u: f32, v: f32) -> Color {
+        self.sample_level(0, u, v)
+    }
+
+    /// Get color at given texture coordinates filtered over the pixel footprint, which is given
+    /// by derivatives of the coordinates one pixel right and one pixel up on the screen.
+    pub fn sample_with_derivatives(&self, u: f32, v: f32, duv_dx: (f32, f32), duv_dy: (f32, f32)) -> Color {
+        if self.filter != TextureFilter::Trilinear {
+            return self.sample(u, v);
+        }
+
+        // Level of detail is chosen by the longer axis of the footprint measured in texels
+        let width = self.levels[0].get_width() as f32;
+        let height = self.levels[0].get_height() as f32;
+        let length_x = ((duv_dx.0 * width).powi(2) + (duv_dx.1 * height).powi(2)).sqrt();
+        let length_y = ((duv_dy.0 * width).powi(2) + (duv_dy.1 * height).powi(2)).sqrt();
+        let lod = length_x.max(length_y).max(1.0).log2().min((self.levels.len() - 1) as f32);
+
+        let level = lod.floor() as usize;
+        let t = lod - lod.floor();
+        let color = self.sample_level(level, u, v);
+        if t <= 0.0 {
+            return color;
+        }
+
+        return Color::lerp(&color, &self.sample_level(level + 1, u, v), t);
+    }
+
+    fn sample_level(&self, level: usize, u: f32, v: f32) -> Color {
+        let image = &self.levels[level];
+        let width = image.get_width();
+        let height = image.get_height();
+
+        // Texel centers lie at half-integer coordinates
+        let x = u * width as f32;
+        let y = (1.0 - v) * height as f32;
+
+        match self.filter {
+            TextureFilter::Nearest => {
+                image.get_pixel(self.wrap_index(x.floor() as i32, width), self.wrap_index(y.floor() as i32, height))
+            },
+            TextureFilter::Bilinear | TextureFilter::Trilinear => {
+                let x = x - 0.5;
+                let y = y - 0.5;
+                let tx = x - x.floor();
+                let ty = y - y.floor();
+
+                // Casts saturate huge and infinite coordinates, so the neighbours must not overflow
+                let x0 = self.wrap_index(x.floor() as i32, width);
+                let x1 = self.wrap_index((x.floor() as i32).saturating_add(1), width);
+                let y0 = self.wrap_index(y.floor() as i32, height);
+                let y1 = self.wrap_index((y.floor() as i32).saturating_add(1), height);
+
+                bilinear(
+                    [
+                        image.get_pixel(x0, y0), image.get_pixel(x1, y0),
+                        image.get_pixel(x0, y1), image.get_pixel(x1, y1)
+                    ],
+                    tx, ty
+                )
+            }
+        }
+    }
+
+    fn wrap_index(&self, index: i32, size: usize) -> usize {
+        let size = size as i32;
+
+        let index = match self.wrap {
+            TextureWrap::Repeat => index.rem_euclid(size),
+            TextureWrap::Clamp => index.clamp(0, size - 1),
+            TextureWrap::Mirror => {
+                // Every other repetition is flipped
+                let index = index.rem_euclid(2 * size);
+                if index < size { index } else { 2 * size - 1 - index }
+            }
+        };
+
+        return index as usize;
+    }
+}
+
+/// Halve the image by averaging 2x2 texel blocks, the last row or column of odd sizes
+/// is dropped. Returns `None` when the image is already 1x1.
+fn downsample(image: &Image<Color>) -> Option<Image<Color>> {
+    let width = image.get_width();
+    let height = image.get_height();
+    if width <= 1 && height <= 1 {
+        return None;
+    }
+
+    let half_width = (width / 2).max(1);
+    let half_height = (height / 2).max(1);
+
+    let mut pixels = Vec::with_capacity(half_width * half_height);
+    for y in 0..half_height {
+        let y0 = (y * 2).min(height - 1);
+        let y1 = (y * 2 + 1).min(height - 1);
+
+        for x in 0..half_width {
+            let x0 = (x * 2).min(width - 1);
+            let x1 = (x * 2 + 1).min(width - 1);
+
+            pixels.push(bilinear(
+                [image.get_pixel(x0, y0), image.get_pixel(x1, y0), image.get_pixel(x0, y1), image.get_pixel(x1, y1)],
+                0.5, 0.5
+            ));
+        }
+    }
+
+    return Some(Image::new(half_width, half_height, pixels));
+}
+
+/// Interpolate four texels given row by row, keeping the precision of all channels.
+fn bilinear(texels: [Color; 4], tx: f32, ty: f32) -> Color {
+    let weights = [(1.0 - tx) * (1.0 - ty), tx * (1.0 - ty), (1.0 - tx) * ty, tx * ty];
+
+    let mut channels = [0.0; 4];
+    for (texel, weight) in texels.iter().zip(weights) {
+        channels[0] += texel.r as f32 * weight;
+        channels[1] += texel.g as f32 * weight;
+        channels[2] += texel.b as f32 * weight;
+        channels[3] += texel.a as f32 * weight;
+    }
+
+    return Color::new_with_alpha(
+        channels[0].round() as u8,
+        channels[1].round() as u8,
+        channels[2].round() as u8,
+        channels[3].round() as u8
+    );
+}
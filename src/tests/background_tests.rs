@@ -0,0 +1,38 @@
+use crate::rendering::background::Background;
+use crate::utils::approximately;
+use crate::utils::color::Color;
+use crate::utils::vector::{Vector3, UP_VECTOR};
+
+#[test]
+fn test_gradient_background() {
+    let background = Background::Gradient {
+        horizon_color: Color::new(255, 255, 255),
+        zenith_color: Color::new(0, 0, 255)
+    };
+
+    assert!(approximately(background.sample(&UP_VECTOR, None).r, 0.0));
+    assert!(approximately(background.sample(&Vector3::new(1.0, 0.0, 0.0), None).r, 1.0));
+    assert!(approximately(background.sample(&-UP_VECTOR, None).g, 1.0));
+}
+
+#[test]
+fn test_sky_sun_disc() {
+    let background = Background::Sky {
+        zenith_color: Color::new(0, 0, 255),
+        horizon_color: Color::new(127, 127, 255),
+        ground_color: Color::new(50, 50, 50),
+        sun_color: Color::new(255, 255, 255),
+        sun_angle: 2.0,
+        sun_intensity: 10.0
+    };
+    let sun = Vector3::new(1.0, 1.0, 0.0).normalized();
+
+    let at_sun = background.sample(&sun, Some(&sun));
+    let away_from_sun = background.sample(&Vector3::new(-1.0, 1.0, 0.0), Some(&sun));
+    let without_sun = background.sample(&sun, None);
+
+    assert!(at_sun.r > 10.0);
+    assert!(away_from_sun.r < 1.0);
+    assert!(without_sun.r < 1.0);
+    assert!(approximately(background.sample(&-UP_VECTOR, Some(&sun)).g, 50.0 / 255.0));
+}
@@ -0,0 +1,84 @@
+use crate::rendering::brdf::{evaluate_brdf, sample_brdf};
+use crate::rendering::lightning::ShadingPoint;
+use crate::rendering::material::Reflectance;
+use crate::utils::approximately;
+use crate::utils::color::{Color, BLACK_HDR_COLOR};
+use crate::utils::random::Random;
+use crate::utils::vector::{Vector3, UP_VECTOR};
+
+fn get_point(reflectance: Reflectance) -> ShadingPoint {
+    ShadingPoint {
+        position: Vector3::default(),
+        normal: UP_VECTOR,
+        view_direction: Vector3::new(1.0, 1.0, 0.0).normalized(),
+        base_color: Color::new(255, 255, 255),
+        reflectance
+    }
+}
+
+#[test]
+fn test_diffuse_brdf() {
+    let point = get_point(Reflectance::Diffuse);
+
+    assert!(approximately(evaluate_brdf(&point, &UP_VECTOR).r, 1.0));
+    assert!(approximately(evaluate_brdf(&point, &Vector3::new(0.75_f32.sqrt(), 0.5, 0.0)).g, 0.5));
+    assert!(approximately(evaluate_brdf(&point, &-UP_VECTOR).b, 0.0));
+}
+
+#[test]
+fn test_blinn_phong_highlight() {
+    let point = get_point(Reflectance::BlinnPhong {
+        specular_color: Color::new(255, 255, 255),
+        shininess: 64.0
+    });
+
+    let mirror = evaluate_brdf(&point, &Vector3::new(-1.0, 1.0, 0.0).normalized());
+    let off_mirror = evaluate_brdf(&point, &Vector3::new(1.0, 1.0, 0.0).normalized());
+
+    // At the mirror direction the half vector matches the normal
+    assert!(approximately(mirror.r - off_mirror.r, 1.0));
+}
+
+#[test]
+fn test_metallic_has_no_diffuse() {
+    let point = get_point(Reflectance::CookTorrance { metallic: 1.0, roughness: 0.1 });
+
+    // Far from the mirror direction only the diffuse term could contribute
+    let color = evaluate_brdf(&point, &Vector3::new(1.0, 0.2, 0.0).normalized());
+    assert!(color.max_component() < 0.01);
+}
+
+#[test]
+fn test_cook_torrance_conserves_energy() {
+    let point = get_point(Reflectance::CookTorrance { metallic: 0.0, roughness: 0.5 });
+    let mut random = Random::default();
+
+    let samples_count = 20000;
+    let mut total = BLACK_HDR_COLOR;
+    for _ in 0..samples_count {
+        if let Some((direction, weight)) = sample_brdf(&point, &mut random) {
+            assert!(Vector3::dot(&direction, &point.normal) > 0.0);
+            total += weight;
+        }
+    }
+
+    let albedo = total.r / samples_count as f32;
+    assert!(albedo > 0.8 && albedo < 1.02);
+}
+
+#[test]
+fn test_blinn_phong_sampling_is_bounded() {
+    let mut point = get_point(Reflectance::BlinnPhong {
+        specular_color: Color::new(255, 255, 255),
+        shininess: 64.0
+    });
+    // Grazing view puts the highlight at grazing light directions
+    point.view_direction = Vector3::new(1.0, 0.05, 0.0).normalized();
+    let mut random = Random::default();
+
+    for _ in 0..20000 {
+        if let Some((_, weight)) = sample_brdf(&point, &mut random) {
+            assert!(weight.max_component() < 10.0);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::utils::color::{Color, PixelData};
+use crate::utils::color::{Color, ColorRamp, HdrColor, PixelData};
 
 #[test]
 fn test_from_pixel_data() {
@@ -25,3 +25,24 @@ fn test_color_lerp() {
     assert_eq!(Color::lerp(&a, &b, 1.0), b);
     assert_eq!(Color::lerp(&a, &b, 0.5), Color::new(64, 159, 40));
 }
+
+#[test]
+fn test_hdr_color_conversion() {
+    let c = Color::new(255, 51, 0);
+    assert_eq!(Color::from(HdrColor::from(c)), c);
+    assert_eq!(Color::from(HdrColor::new(2.0, -1.0, 0.5)), Color::new(255, 0, 127));
+}
+
+#[test]
+fn test_color_ramp() {
+    let ramp = ColorRamp::new(vec![
+        (1.0, Color::new(0, 0, 200)),
+        (0.25, Color::new(100, 0, 0)),
+        (0.75, Color::new(200, 0, 0))
+    ]);
+
+    assert_eq!(ramp.sample(0.0), Color::new(100, 0, 0));
+    assert_eq!(ramp.sample(0.5), Color::new(150, 0, 0));
+    assert_eq!(ramp.sample(0.875), Color::new(100, 0, 100));
+    assert_eq!(ramp.sample(2.0), Color::new(0, 0, 200));
+}
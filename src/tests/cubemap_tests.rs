@@ -0,0 +1,62 @@
+use crate::image::Image;
+use crate::rendering::cubemap::{Cubemap, direction_to_face_uv, face_uv_to_direction};
+use crate::utils::approximately;
+use crate::utils::color::HdrColor;
+use crate::utils::vector::Vector3;
+
+fn create_uniform_cubemap(size: usize) -> Cubemap {
+    let faces = std::array::from_fn(|face| {
+        Image::new(size, size, vec![HdrColor::new(face as f32, 0.0, 0.0); size * size])
+    });
+    Cubemap::new(faces).unwrap()
+}
+
+#[test]
+fn test_face_uv_round_trip() {
+    let direction = Vector3::new(0.3, -0.8, 0.5);
+    let (face, u, v) = direction_to_face_uv(&direction);
+    assert_eq!(face, 3);
+
+    let result = face_uv_to_direction(face, u, v).normalized();
+    assert!(approximately(Vector3::dot(&result, &direction.normalized()), 1.0));
+}
+
+#[test]
+fn test_cubemap_faces() {
+    let cubemap = create_uniform_cubemap(4);
+
+    assert!(approximately(cubemap.sample(&Vector3::new(1.0, 0.1, 0.1)).r, 0.0));
+    assert!(approximately(cubemap.sample(&Vector3::new(-1.0, 0.1, 0.1)).r, 1.0));
+    assert!(approximately(cubemap.sample(&Vector3::new(0.1, 1.0, 0.1)).r, 2.0));
+    assert!(approximately(cubemap.sample(&Vector3::new(0.1, -1.0, 0.1)).r, 3.0));
+    assert!(approximately(cubemap.sample(&Vector3::new(0.1, 0.1, 1.0)).r, 4.0));
+    assert!(approximately(cubemap.sample(&Vector3::new(0.1, 0.1, -1.0)).r, 5.0));
+}
+
+#[test]
+fn test_cubemap_seam() {
+    let cubemap = create_uniform_cubemap(4);
+
+    // Edge between +X and +Z blends both faces evenly from either side
+    let on_x = cubemap.sample(&Vector3::new(1.0, 0.0, 0.999));
+    let on_z = cubemap.sample(&Vector3::new(0.999, 0.0, 1.0));
+    assert!((on_x.r - 2.0).abs() < 0.01);
+    assert!((on_z.r - 2.0).abs() < 0.01);
+}
+
+#[test]
+fn test_cubemap_from_cross() {
+    let size = 2;
+    let pixels = (0..size * 4 * size * 3)
+        .map(|i| HdrColor::new(((i % (size * 4)) / size) as f32, ((i / (size * 4)) / size) as f32, 0.0))
+        .collect();
+    let cubemap = Cubemap::from_cross(&Image::new(size * 4, size * 3, pixels)).unwrap();
+
+    // +Y is at the top of the second column, -Z at the end of the middle row
+    let up = cubemap.sample(&Vector3::new(0.0, 1.0, 0.0));
+    assert!(approximately(up.r, 1.0) && approximately(up.g, 0.0));
+    let back = cubemap.sample(&Vector3::new(0.0, 0.0, -1.0));
+    assert!(approximately(back.r, 3.0) && approximately(back.g, 1.0));
+
+    assert!(Cubemap::from_cross(&Image::new(3, 3, vec![HdrColor::new(0.0, 0.0, 0.0); 9])).is_err());
+}
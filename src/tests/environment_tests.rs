@@ -0,0 +1,34 @@
+use crate::image::Image;
+use crate::rendering::environment::EnvironmentMap;
+use crate::utils::approximately;
+use crate::utils::color::HdrColor;
+use crate::utils::vector::{Vector3, UP_VECTOR};
+
+#[test]
+fn test_uniform_environment_irradiance() {
+    let image = Image::new(32, 16, vec![HdrColor::new(0.5, 0.5, 0.5); 32 * 16]);
+    let environment = EnvironmentMap::new(image);
+
+    assert!(approximately(environment.sample(&Vector3::new(0.3, -0.2, 0.9)).r, 0.5));
+    // Projection of the tiny image leaks a bit into higher bands
+    assert!((environment.compute_irradiance(&UP_VECTOR).g - 0.5).abs() < 0.005);
+    assert!((environment.compute_irradiance(&Vector3::new(1.0, 1.0, 0.0)).b - 0.5).abs() < 0.005);
+}
+
+#[test]
+fn test_sky_environment_irradiance() {
+    // Bright upper half and dark lower half
+    let pixels = (0..32 * 16)
+        .map(|i| if i / 32 < 8 { HdrColor::new(1.0, 1.0, 1.0) } else { HdrColor::new(0.0, 0.0, 0.0) })
+        .collect();
+    let environment = EnvironmentMap::new(Image::new(32, 16, pixels));
+
+    assert!(environment.sample(&UP_VECTOR).r > 0.99);
+    assert!(environment.sample(&-UP_VECTOR).r < 0.01);
+
+    let up = environment.compute_irradiance(&UP_VECTOR).r;
+    let side = environment.compute_irradiance(&Vector3::new(1.0, 0.0, 0.0)).r;
+    let down = environment.compute_irradiance(&-UP_VECTOR).r;
+    assert!(up > 0.9 && down < 0.1);
+    assert!((side - 0.5).abs() < 0.05);
+}
@@ -0,0 +1,36 @@
+use crate::rendering::fog::{Fog, FogFalloff};
+use crate::utils::approximately;
+use crate::utils::vector::{Vector3, ZERO_VECTOR as ORIGIN, FORWARD_VECTOR as FORWARD};
+
+#[test]
+fn test_linear_fog() {
+    let fog = Fog { falloff: FogFalloff::Linear { start: 2.0, end: 6.0 }, ..Default::default() };
+
+    assert!(approximately(fog.compute_fog_amount(&ORIGIN, &FORWARD, 1.0), 0.0));
+    assert!(approximately(fog.compute_fog_amount(&ORIGIN, &FORWARD, 4.0), 0.5));
+    assert!(approximately(fog.compute_fog_amount(&ORIGIN, &FORWARD, 10.0), 1.0));
+}
+
+#[test]
+fn test_exponential_fog() {
+    let fog = Fog { falloff: FogFalloff::Exponential, density: 0.5, ..Default::default() };
+    assert!(approximately(fog.compute_fog_amount(&ORIGIN, &FORWARD, 2.0), 1.0 - (-1.0f32).exp()));
+
+    let fog = Fog { falloff: FogFalloff::ExponentialSquared, density: 0.5, ..Default::default() };
+    assert!(approximately(fog.compute_fog_amount(&ORIGIN, &FORWARD, 4.0), 1.0 - (-4.0f32).exp()));
+}
+
+#[test]
+fn test_height_fog() {
+    let fog = Fog { density: 0.5, height_falloff: Some(1.0), height: 0.0, ..Default::default() };
+    let uniform = Fog { density: 0.5, ..Default::default() };
+
+    // Horizontal ray at the fog height sees the base density
+    let amount = fog.compute_fog_amount(&ORIGIN, &FORWARD, 3.0);
+    assert!(approximately(amount, uniform.compute_fog_amount(&ORIGIN, &FORWARD, 3.0)));
+
+    // Looking up the fog thins out, looking down it thickens
+    let up = fog.compute_fog_amount(&ORIGIN, &Vector3::new(0.0, 1.0, 1.0), 3.0);
+    let down = fog.compute_fog_amount(&ORIGIN, &Vector3::new(0.0, -1.0, 1.0), 3.0);
+    assert!(up < amount && amount < down);
+}
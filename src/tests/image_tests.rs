@@ -0,0 +1,148 @@
+use crate::image::{decode_image, hdr::decode_hdr, inflate::zlib_decompress};
+use crate::utils::approximately;
+use crate::utils::color::Color;
+
+fn get_header(width: usize, height: usize) -> Vec<u8> {
+    format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", height, width).into_bytes()
+}
+
+#[test]
+fn test_decode_flat_hdr() {
+    let mut data = get_header(2, 1);
+    // 1.0 is stored as mantissa 128 with exponent 129
+    data.extend_from_slice(&[128, 0, 0, 129, 0, 0, 128, 130]);
+
+    let image = decode_hdr(&data).unwrap();
+    assert_eq!(image.get_width(), 2);
+    assert!(approximately(image.get_pixel(0, 0).r, 1.0));
+    assert!(approximately(image.get_pixel(1, 0).b, 2.0));
+}
+
+#[test]
+fn test_decode_rle_hdr() {
+    let width = 8;
+    let mut data = get_header(width, 1);
+    data.extend_from_slice(&[2, 2, 0, width as u8]);
+    // Red as a run, green and blue as literal spans, exponent as two runs
+    data.extend_from_slice(&[128 + 8, 64]);
+    data.extend_from_slice(&[8, 0, 1, 2, 3, 4, 5, 6, 7]);
+    data.extend_from_slice(&[8, 0, 0, 0, 0, 0, 0, 0, 0]);
+    data.extend_from_slice(&[128 + 4, 129, 128 + 4, 130]);
+
+    let image = decode_hdr(&data).unwrap();
+    assert!(approximately(image.get_pixel(0, 0).r, 0.5));
+    assert!(approximately(image.get_pixel(7, 0).r, 1.0));
+    assert!(image.get_pixel(7, 0).g > image.get_pixel(6, 0).g);
+}
+
+#[test]
+fn test_decode_corrupt_hdr() {
+    assert!(decode_hdr(b"P6\n").is_err());
+
+    let mut data = get_header(2, 2);
+    data.extend_from_slice(&[128, 0, 0, 129]);
+    assert!(decode_hdr(&data).is_err());
+
+    // Huge resolution must be rejected before allocating the pixels
+    let mut data = get_header(100000, 100000);
+    data.extend_from_slice(&[128, 0, 0, 129]);
+    assert!(decode_hdr(&data).is_err());
+    assert!(decode_hdr(&get_header(usize::MAX, 2)).is_err());
+    assert!(decode_hdr(&get_header(usize::MAX, 1)).is_err());
+}
+
+const TEXT: &[u8] = b"the quick brown fox jumps over the lazy dog while the lazy dog sleeps under the quick brown fox";
+
+/// `TEXT` compressed with dynamic Huffman codes.
+const COMPRESSED_TEXT: [u8; 70] = [
+    120, 218, 93, 140, 65, 22, 64, 48, 16, 197, 174, 242, 175, 134, 126, 90, 70, 135, 182, 163,
+    56, 189, 46, 108, 216, 38, 121, 41, 158, 216, 45, 12, 11, 250, 164, 53, 98, 212, 19, 179,
+    173, 91, 134, 30, 76, 40, 77, 75, 119, 95, 112, 58, 161, 250, 32, 252, 162, 44, 100, 107,
+    45, 186, 55, 254, 189, 30, 145, 182, 35, 11
+];
+
+/// 2x2 RGBA image with red, half transparent green, blue and transparent yellow pixels.
+/// The second row uses the sub filter.
+const PNG_IMAGE: [u8; 79] = [
+    137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 2, 0, 0, 0, 2, 8, 6,
+    0, 0, 0, 114, 182, 13, 36, 0, 0, 0, 22, 73, 68, 65, 84, 120, 218, 99, 248, 207, 192, 240,
+    31, 8, 27, 24, 129, 52, 16, 48, 50, 2, 0, 65, 225, 7, 125, 213, 43, 60, 142, 0, 0, 0, 0,
+    73, 69, 78, 68, 174, 66, 96, 130
+];
+
+/// 3x3 interlaced RGB image, where red grows along X and green along Y.
+const INTERLACED_PNG_IMAGE: [u8; 91] = [
+    137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 3, 0, 0, 0, 3, 8, 2,
+    0, 0, 1, 174, 77, 18, 126, 0, 0, 0, 34, 73, 68, 65, 84, 120, 156, 13, 198, 49, 13, 0, 0, 12,
+    132, 64, 52, 161, 9, 177, 47, 171, 29, 200, 1, 200, 62, 230, 38, 253, 246, 146, 229, 242, 0, 130, 3, 8,
+    203, 183, 10, 143, 13, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96, 130
+];
+
+#[test]
+fn test_zlib_decompress() {
+    assert_eq!(zlib_decompress(&COMPRESSED_TEXT, TEXT.len()).unwrap(), TEXT);
+    assert!(zlib_decompress(&COMPRESSED_TEXT, TEXT.len() - 1).is_err());
+
+    let mut corrupt = COMPRESSED_TEXT;
+    corrupt[69] ^= 1;
+    assert!(zlib_decompress(&corrupt, TEXT.len()).is_err());
+    assert!(zlib_decompress(&COMPRESSED_TEXT[..40], TEXT.len()).is_err());
+}
+
+#[test]
+fn test_decode_png() {
+    let image = decode_image(&PNG_IMAGE).unwrap();
+
+    assert_eq!(image.get_width(), 2);
+    assert_eq!(image.get_pixel(0, 0), Color::new(255, 0, 0));
+    assert_eq!(image.get_pixel(1, 0), Color::new_with_alpha(0, 255, 0, 128));
+    assert_eq!(image.get_pixel(0, 1), Color::new(0, 0, 255));
+    assert_eq!(image.get_pixel(1, 1), Color::new_with_alpha(255, 255, 0, 0));
+
+    let image = decode_image(&INTERLACED_PNG_IMAGE).unwrap();
+    assert_eq!(image.get_pixel(0, 0), Color::new(0, 0, 50));
+    assert_eq!(image.get_pixel(2, 1), Color::new(200, 100, 50));
+    assert_eq!(image.get_pixel(1, 2), Color::new(100, 200, 50));
+}
+
+#[test]
+fn test_decode_corrupt_png() {
+    // Flipped bit in the image data breaks the chunk checksum
+    let mut data = PNG_IMAGE;
+    data[50] ^= 4;
+    assert!(decode_image(&data).is_err());
+    assert!(decode_image(&PNG_IMAGE[..60]).is_err());
+}
+
+#[test]
+fn test_decode_bmp() {
+    // 2x2 bottom-up 24 bit bitmap, rows are padded to 8 bytes
+    let mut data = b"BM".to_vec();
+    data.extend_from_slice(&[70, 0, 0, 0, 0, 0, 0, 0, 54, 0, 0, 0]);
+    data.extend_from_slice(&[40, 0, 0, 0, 2, 0, 0, 0, 2, 0, 0, 0, 1, 0, 24, 0]);
+    data.extend_from_slice(&[0; 24]);
+    data.extend_from_slice(&[255, 0, 0, 0, 255, 0, 0, 0]);
+    data.extend_from_slice(&[0, 0, 255, 255, 255, 255, 0, 0]);
+
+    let image = decode_image(&data).unwrap();
+    assert_eq!(image.get_pixel(0, 0), Color::new(255, 0, 0));
+    assert_eq!(image.get_pixel(1, 0), Color::new(255, 255, 255));
+    assert_eq!(image.get_pixel(0, 1), Color::new(0, 0, 255));
+
+    assert!(decode_image(&data[..60]).is_err());
+}
+
+#[test]
+fn test_decode_ppm() {
+    let ascii = decode_image(b"P3\n# comment\n2 1\n15\n15 0 0  0 15 0\n").unwrap();
+    assert_eq!(ascii.get_pixel(0, 0), Color::new(255, 0, 0));
+    assert_eq!(ascii.get_pixel(1, 0), Color::new(0, 255, 0));
+
+    let binary = decode_image(b"P6 1 1 255\n\x10\x20\x30").unwrap();
+    assert_eq!(binary.get_pixel(0, 0), Color::new(16, 32, 48));
+
+    assert!(decode_image(b"P3 1 1 255 1 2").is_err());
+    assert!(decode_image(b"P6 2 2 255\n\x10\x20").is_err());
+    assert!(decode_image(b"P6 3074457345618258603 1 65535\n\x10\x20").is_err());
+    assert!(decode_image(b"GIF89a").is_err());
+}
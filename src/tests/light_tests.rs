@@ -0,0 +1,91 @@
+use crate::rendering::light::Light;
+use crate::utils::approximately;
+use crate::utils::color::Color;
+use crate::utils::random::Random;
+use crate::utils::vector::{Vector3, UP_VECTOR};
+
+#[test]
+fn test_directional_light() {
+    let light = Light::new_directional(&Vector3::new(0.0, -2.0, 0.0), &Color::new(255, 255, 255), 0.5);
+    let sample = light.illuminate(&Vector3::new(10.0, -3.0, 4.0)).unwrap();
+
+    assert!(sample.direction.approximately(&UP_VECTOR));
+    assert!(sample.distance.is_infinite());
+    assert!(approximately(sample.color.r, 0.5));
+}
+
+#[test]
+fn test_point_light_attenuation() {
+    let light = Light::new_point(&Vector3::new(0.0, 2.0, 0.0), &Color::new(255, 255, 255), 1.0);
+
+    let near = light.illuminate(&Vector3::new(0.0, 1.0, 0.0)).unwrap();
+    let far = light.illuminate(&Vector3::new(0.0, 0.0, 0.0)).unwrap();
+
+    assert!(near.direction.approximately(&UP_VECTOR));
+    assert!(approximately(near.distance, 1.0));
+    assert!(approximately(near.color.g, 1.0));
+    assert!(approximately(far.color.g, 0.25));
+
+    // Point at the light itself has no direction to it
+    assert!(light.illuminate(&Vector3::new(0.0, 2.0, 0.0)).is_none());
+}
+
+#[test]
+fn test_spot_light_cone() {
+    let light = Light::new_spot(
+        &Vector3::new(0.0, 1.0, 0.0),
+        &Vector3::new(0.0, -1.0, 0.0),
+        20.0,
+        40.0,
+        &Color::new(255, 255, 255),
+        1.0
+    );
+
+    let center = light.illuminate(&Vector3::new(0.0, 0.0, 0.0)).unwrap();
+    assert!(approximately(center.color.b, 1.0));
+
+    // 30 degrees off the axis is halfway between inner and outer angles
+    let edge = light.illuminate(&Vector3::new((30.0_f32).to_radians().tan(), 0.0, 0.0)).unwrap();
+    assert!(edge.color.b > 0.0 && edge.color.b < center.color.b);
+
+    assert!(light.illuminate(&Vector3::new(2.0, 0.0, 0.0)).is_none());
+}
+
+#[test]
+fn test_rect_light_samples() {
+    let light = Light::new_rect(
+        &Vector3::new(0.0, 2.0, 0.0),
+        &Vector3::new(0.0, -1.0, 0.0),
+        1.0,
+        0.5,
+        &Color::new(255, 255, 255),
+        1.0
+    );
+    let mut random = Random::default();
+    let position = Vector3::default();
+
+    for _ in 0..100 {
+        let sample = light.sample(&position, &mut random).unwrap();
+        let light_point = sample.direction * sample.distance;
+
+        assert!(approximately(light_point.y, 2.0));
+        assert!(light_point.x.abs() <= 0.5 && light_point.z.abs() <= 0.5);
+    }
+
+    // Rect light does not shine backwards
+    assert!(light.sample(&Vector3::new(0.0, 3.0, 0.0), &mut random).is_none());
+}
+
+#[test]
+fn test_sphere_light_samples() {
+    let light = Light::new_sphere(&Vector3::new(0.0, 2.0, 0.0), 0.5, &Color::new(255, 255, 255), 1.0);
+    let mut random = Random::default();
+    let position = Vector3::default();
+
+    for _ in 0..100 {
+        let sample = light.sample(&position, &mut random).unwrap();
+        let light_point = sample.direction * sample.distance;
+
+        assert!((light_point - Vector3::new(0.0, 2.0, 0.0)).length() <= 0.5 + 0.0001);
+    }
+}
@@ -0,0 +1,28 @@
+use crate::rendering::lightning::{ToonLightning, ToonRamp};
+use crate::utils::approximately;
+use crate::utils::color::Color;
+
+#[test]
+fn test_toon_bands() {
+    let mut lightning = ToonLightning::new(vec![], &Color::new(0, 0, 0));
+    lightning.set_ramp(ToonRamp::Bands(4));
+
+    assert!(approximately(lightning.quantize(0.0).r, 0.0));
+    assert!(approximately(lightning.quantize(0.1).r, 0.25));
+    assert!(approximately(lightning.quantize(0.6).g, 0.75));
+    assert!(approximately(lightning.quantize(1.0).b, 1.0));
+}
+
+#[test]
+fn test_toon_color_ramp() {
+    let mut lightning = ToonLightning::new(vec![], &Color::new(0, 0, 0));
+    lightning.set_ramp(ToonRamp::Colors(vec![
+        Color::new(0, 0, 255),
+        Color::new(0, 255, 0),
+        Color::new(255, 0, 0)
+    ]));
+
+    assert!(approximately(lightning.quantize(0.1).b, 1.0));
+    assert!(approximately(lightning.quantize(0.5).g, 1.0));
+    assert!(approximately(lightning.quantize(1.0).r, 1.0));
+}
@@ -0,0 +1,120 @@
+use crate::image::Image;
+use crate::raycaster::{Raycaster, BoxRaycaster};
+use crate::rendering::material::{Material, Reflectance};
+use crate::rendering::material_graph::{GraphMaterial, MaterialGraph, MathOperation, NodeKind, OUTPUT_NODE_INDEX};
+use crate::rendering::texture::Texture;
+use crate::utils::color::{Color, ColorRamp};
+use crate::utils::vector::Vector3;
+
+#[test]
+fn test_graph_evaluation() {
+    let mut graph = MaterialGraph::new([0.0, 0.0]);
+    let checker = graph.add_node(NodeKind::Checker, [0.0, 0.0]);
+    let mix = graph.add_node(NodeKind::Mix, [0.0, 0.0]);
+    let half = graph.add_node(NodeKind::Math(MathOperation::Multiply), [0.0, 0.0]);
+
+    // Checker picks between red and blue, alpha is half of the checker value
+    graph.get_mut_node(mix).unwrap().inputs[0].value = Vector3::new(1.0, 0.0, 0.0);
+    graph.get_mut_node(mix).unwrap().inputs[1].value = Vector3::new(0.0, 0.0, 1.0);
+    graph.get_mut_node(half).unwrap().inputs[1].value = Vector3::new(0.5, 0.5, 0.5);
+    graph.link(checker, mix, 2).unwrap();
+    graph.link(mix, OUTPUT_NODE_INDEX, 0).unwrap();
+    graph.link(checker, half, 0).unwrap();
+    graph.link(half, OUTPUT_NODE_INDEX, 1).unwrap();
+
+    let material = GraphMaterial::new(&graph, 0.0, Reflectance::Diffuse);
+    let raycaster = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0));
+    let direction = Vector3::new(0.0, 0.0, 1.0);
+
+    // Squares are half a unit wide, front face lies at z = -0.5 in the odd row along Z
+    let hit = raycaster.raycast(&Vector3::new(0.25, 0.25, -3.0), &direction).unwrap();
+    assert_eq!(material.compute_surface_color(&hit), Color::new_with_alpha(0, 0, 255, 128));
+    let hit = raycaster.raycast(&Vector3::new(-0.25, 0.25, -3.0), &direction).unwrap();
+    assert_eq!(material.compute_surface_color(&hit), Color::new_with_alpha(255, 0, 0, 0));
+    assert!(!material.is_opaque());
+}
+
+#[test]
+fn test_graph_noise_ramp() {
+    let mut graph = MaterialGraph::new([0.0, 0.0]);
+    let noise = graph.add_node(NodeKind::Noise { seed: 3, octaves_count: 1 }, [0.0, 0.0]);
+    let ramp = graph.add_node(
+        NodeKind::ColorRamp(ColorRamp::new(vec![(0.0, Color::new(0, 0, 0)), (1.0, Color::new(0, 255, 0))])),
+        [0.0, 0.0]
+    );
+    graph.link(noise, ramp, 0).unwrap();
+    graph.link(ramp, OUTPUT_NODE_INDEX, 0).unwrap();
+
+    // Noise is zero at lattice points, which maps to the middle of the ramp
+    let material = GraphMaterial::new(&graph, 0.0, Reflectance::Diffuse);
+    let hit = BoxRaycaster::new(&Vector3::new(2.0, 2.0, 2.0))
+        .raycast(&Vector3::new(0.0, 0.0, -3.0), &Vector3::new(0.0, 0.0, 1.0))
+        .unwrap();
+    let (color, alpha) = material.evaluate(&hit);
+    // Ramp colors are stored in 8 bits
+    assert!((color - Vector3::new(0.0, 0.5, 0.0)).length() < 0.01);
+    assert_eq!(alpha, 1.0);
+    assert!(material.is_opaque());
+}
+
+#[test]
+fn test_graph_links() {
+    let mut graph = MaterialGraph::new([0.0, 0.0]);
+    let first = graph.add_node(NodeKind::Math(MathOperation::Add), [0.0, 0.0]);
+    let second = graph.add_node(NodeKind::Math(MathOperation::Add), [0.0, 0.0]);
+    let third = graph.add_node(NodeKind::Math(MathOperation::Add), [0.0, 0.0]);
+
+    graph.link(first, second, 0).unwrap();
+    graph.link(second, third, 0).unwrap();
+    graph.link(third, OUTPUT_NODE_INDEX, 0).unwrap();
+
+    // Links closing a loop, from the output or to missing inputs are refused
+    assert!(graph.link(third, first, 1).is_err());
+    assert!(graph.link(first, first, 0).is_err());
+    assert!(graph.link(OUTPUT_NODE_INDEX, first, 0).is_err());
+    assert!(graph.link(first, third, 5).is_err());
+
+    // Removing a node drops its links and renumbers the following nodes
+    assert!(!graph.remove_node(OUTPUT_NODE_INDEX));
+    assert!(graph.remove_node(second));
+    assert_eq!(graph.get_nodes().len(), 3);
+    assert_eq!(graph.get_node(second).unwrap().inputs[0].link, None);
+    assert_eq!(graph.get_node(OUTPUT_NODE_INDEX).unwrap().inputs[0].link, Some(second));
+}
+
+#[test]
+fn test_diamond_graph_links() {
+    // Each node feeds both inputs of the next one, so there are 2^64 paths back to the first node
+    let mut graph = MaterialGraph::new([0.0, 0.0]);
+    let first = graph.add_node(NodeKind::Math(MathOperation::Add), [0.0, 0.0]);
+    let mut last = first;
+    for _ in 0..64 {
+        let node = graph.add_node(NodeKind::Math(MathOperation::Add), [0.0, 0.0]);
+        graph.link(last, node, 0).unwrap();
+        graph.link(last, node, 1).unwrap();
+        last = node;
+    }
+
+    assert!(graph.link(last, first, 0).is_err());
+    assert!(graph.link(last, OUTPUT_NODE_INDEX, 0).is_ok());
+}
+
+#[test]
+fn test_texture_node_with_infinite_coordinates() {
+    let mut graph = MaterialGraph::new([0.0, 0.0]);
+    let power = graph.add_node(NodeKind::Math(MathOperation::Power), [0.0, 0.0]);
+    let image = Image::new(2, 1, vec![Color::new(0, 0, 0), Color::new(255, 255, 255)]);
+    let texture = graph.add_node(NodeKind::Texture { path: String::new(), texture: Some(Texture::new(image)) }, [0.0, 0.0]);
+
+    // Power overflows to infinity, which must not break sampling of the texture
+    graph.get_mut_node(power).unwrap().inputs[0].value = Vector3::new(10.0, 10.0, 10.0);
+    graph.get_mut_node(power).unwrap().inputs[1].value = Vector3::new(1.0e6, 1.0e6, 1.0e6);
+    graph.link(power, texture, 0).unwrap();
+    graph.link(texture, OUTPUT_NODE_INDEX, 0).unwrap();
+
+    let material = GraphMaterial::new(&graph, 0.0, Reflectance::Diffuse);
+    let hit = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0))
+        .raycast(&Vector3::new(0.0, 0.0, -3.0), &Vector3::new(0.0, 0.0, 1.0))
+        .unwrap();
+    material.compute_surface_color(&hit);
+}
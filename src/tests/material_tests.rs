@@ -0,0 +1,181 @@
+use crate::image::Image;
+use crate::raycaster::{Raycaster, BoxRaycaster};
+use crate::rendering::bump::{BumpMaterial, BumpPattern};
+use crate::rendering::material::{CheckerMaterial, CutoutMaterial, FaceMaterial, FlatMaterial, MatcapMaterial, Material, NoiseMaterial, NoisePattern, Reflectance};
+use crate::rendering::texture::{Texture, TextureFilter, TextureWrap};
+use crate::utils::approximately;
+use crate::utils::color::{Color, ColorRamp};
+use crate::utils::noise::PerlinNoise;
+use crate::utils::transform::Transform;
+use crate::utils::vector::Vector3;
+
+#[test]
+fn test_checker_box_filter() {
+    let checker = CheckerMaterial {
+        scale: 1.0,
+        first_color: Color::new(255, 255, 255),
+        second_color: Color::new(0, 0, 0),
+        reflectivity: 0.0,
+        reflectance: Reflectance::Diffuse
+    };
+
+    // Without footprint the pattern is sampled exactly
+    assert_eq!(checker.sample_checker_texture(0.25, 0.25, 0.0, 0.0), 0.0);
+    assert_eq!(checker.sample_checker_texture(0.75, 0.25, 0.0, 0.0), 1.0);
+    assert_eq!(checker.sample_checker_texture(-0.25, 0.25, 0.0, 0.0), 1.0);
+
+    // Footprint centered on an edge covers both squares equally
+    assert!(approximately(checker.sample_checker_texture(0.5, 0.25, 0.25, 0.0), 0.5));
+    // Footprint of a whole period averages both colors
+    assert!(approximately(checker.sample_checker_texture(0.3, 0.1, 1.0, 1.0), 0.5));
+
+    // Zero scale gives the finest pattern instead of infinite coordinates
+    let checker = CheckerMaterial { scale: 0.0, ..checker };
+    assert!((0.0..=1.0).contains(&checker.sample_checker_texture(0.3, 0.1, 0.01, 0.01)));
+}
+
+#[test]
+fn test_noise_patterns() {
+    let mut material = NoiseMaterial {
+        pattern: NoisePattern::Perlin,
+        noise: PerlinNoise::new(1),
+        ramp: ColorRamp::new(vec![(0.0, Color::new(0, 0, 0)), (1.0, Color::new(255, 255, 255))]),
+        scale: 0.5,
+        octaves_count: 4,
+        distortion: 0.0,
+        reflectivity: 0.0,
+        reflectance: Reflectance::Diffuse
+    };
+
+    // Noise is zero on the lattice points, which lie every scale units
+    assert!(approximately(material.compute_pattern(&Vector3::new(1.0, 0.5, -1.5)), 0.5));
+
+    // Undistorted wood rings repeat eight times per scale units from the Y axis
+    material.pattern = NoisePattern::Wood;
+    assert!(approximately(material.compute_pattern(&Vector3::new(0.03125, 3.0, 0.0)), 0.5));
+    assert!(approximately(material.compute_pattern(&Vector3::new(0.0, -2.0, 0.15625)), 0.5));
+
+    // Undistorted marble veins are darkest every scale units along X
+    material.pattern = NoisePattern::Marble;
+    assert!(approximately(material.compute_pattern(&Vector3::new(1.0, 0.3, 0.7)), 0.0));
+    assert!(approximately(material.compute_pattern(&Vector3::new(1.25, 0.3, 0.7)), 1.0));
+}
+
+#[test]
+fn test_face_material() {
+    let material = FaceMaterial {
+        faces: std::array::from_fn(|i| -> Box<dyn Material> {
+            Box::new(FlatMaterial { color: Color::new(i as u8, 0, 0), reflectivity: 0.0, reflectance: Reflectance::Diffuse })
+        })
+    };
+    let raycaster = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0));
+
+    // Hit on the -Z face looking from the front
+    let hit = raycaster.raycast(&Vector3::new(0.0, 0.0, -3.0), &Vector3::new(0.0, 0.0, 1.0)).unwrap();
+    assert_eq!(material.compute_surface_color(&hit), Color::new(5, 0, 0));
+    assert_eq!(material.get_hit_material(&hit).unwrap().compute_surface_color(&hit), Color::new(5, 0, 0));
+
+    let hit = raycaster.raycast(&Vector3::new(0.0, 3.0, 0.0), &Vector3::new(0.0, -1.0, 0.0)).unwrap();
+    assert_eq!(material.compute_surface_color(&hit), Color::new(2, 0, 0));
+}
+
+fn create_bump_material(pattern: BumpPattern) -> BumpMaterial {
+    BumpMaterial {
+        material: Box::new(FlatMaterial { color: Color::new(255, 255, 255), reflectivity: 0.0, reflectance: Reflectance::Diffuse }),
+        pattern,
+        scale: 1.0,
+        strength: 1.0
+    }
+}
+
+#[test]
+fn test_normal_map() {
+    let raycaster = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0));
+    let hit = raycaster.raycast(&Vector3::new(0.1, 0.2, -3.0), &Vector3::new(0.0, 0.0, 1.0)).unwrap();
+    let (tangent, _) = hit.get_tangent_frame();
+
+    // Flat normal map keeps the geometric normal, up to rounding of the stored channels
+    let flat = Texture::new(Image::new(1, 1, vec![Color::new(128, 128, 255)]));
+    let normal = create_bump_material(BumpPattern::NormalMap(flat)).compute_normal(&hit).unwrap();
+    assert!((normal - hit.local_normal).length() < 0.01);
+
+    // Red channel tilts the normal toward the tangent
+    let tilted = Texture::new(Image::new(1, 1, vec![Color::new(218, 128, 218)]));
+    let normal = create_bump_material(BumpPattern::NormalMap(tilted)).compute_normal(&hit).unwrap();
+    assert!(approximately(normal.length(), 1.0));
+    assert!(Vector3::dot(&normal, &tangent) > 0.5);
+    assert!(Vector3::dot(&normal, &hit.local_normal) > 0.5);
+}
+
+#[test]
+fn test_bricks_relief() {
+    let raycaster = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0));
+    let mut material = create_bump_material(BumpPattern::Bricks);
+    let cast = |x: f32, y: f32| raycaster.raycast(&Vector3::new(x, y, -3.0), &Vector3::new(0.0, 0.0, 1.0)).unwrap();
+
+    // Middle of a brick is flat
+    let hit = cast(0.125, 0.0625);
+    assert!(approximately(hit.uv.0.rem_euclid(0.25), 0.125) && approximately(hit.uv.1.rem_euclid(0.125), 0.0625));
+    assert!(material.compute_normal(&hit).unwrap().approximately(&hit.local_normal));
+
+    // Bevels at the brick edges tilt the normal away from the brick
+    let (_, bitangent) = hit.get_tangent_frame();
+    let edge = cast(0.125, 0.0625 + 0.046875);
+    let normal = material.compute_normal(&edge).unwrap();
+    assert!(approximately(normal.length(), 1.0));
+    assert!(Vector3::dot(&normal, &bitangent) > 0.01);
+
+    // Zero strength keeps the surface flat
+    material.strength = 0.0;
+    assert!(material.compute_normal(&edge).unwrap().approximately(&edge.local_normal));
+}
+
+#[test]
+fn test_cutout_opacity() {
+    let raycaster = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0));
+    let hit = raycaster.raycast(&Vector3::new(0.0, 0.0, -3.0), &Vector3::new(0.0, 0.0, 1.0)).unwrap();
+    let color = Color::new_with_alpha(255, 255, 255, 102);
+
+    // Alpha of the surface color gives its opacity
+    let flat = FlatMaterial { color, reflectivity: 0.0, reflectance: Reflectance::Diffuse };
+    assert!(approximately(flat.compute_opacity(&hit), 0.4));
+
+    // Cutout makes the surface either opaque or a hole
+    let mut cutout = CutoutMaterial {
+        material: Box::new(FlatMaterial { color, reflectivity: 0.0, reflectance: Reflectance::Diffuse }),
+        threshold: 0.3
+    };
+    assert_eq!(cutout.compute_opacity(&hit), 1.0);
+    cutout.threshold = 0.5;
+    assert_eq!(cutout.compute_opacity(&hit), 0.0);
+    assert!(!cutout.is_opaque());
+
+    // Opaque images let the renderer skip the opacity of the surface
+    let pixels = vec![Color::new(255, 0, 0), color];
+    assert!(Texture::new(Image::new(1, 1, vec![Color::new(255, 0, 0)])).is_opaque());
+    assert!(!Texture::new(Image::new(2, 1, pixels)).is_opaque());
+}
+
+#[test]
+fn test_matcap_lookup() {
+    assert_eq!(MatcapMaterial::get_matcap_uv(&Vector3::new(0.0, 0.0, -1.0)), (0.5, 0.5));
+    assert_eq!(MatcapMaterial::get_matcap_uv(&Vector3::new(1.0, 0.0, 0.0)), (1.0, 0.5));
+    assert_eq!(MatcapMaterial::get_matcap_uv(&Vector3::new(0.0, 1.0, 0.0)), (0.5, 1.0));
+
+    // Top and left sides of the clay sphere face its light
+    let texture = Texture::new(MatcapMaterial::create_clay_image(32))
+        .with_sampling(TextureFilter::Bilinear, TextureWrap::Clamp);
+    let material = MatcapMaterial { texture };
+    let raycaster = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0));
+    let top_hit = raycaster.raycast(&Vector3::new(0.0, 3.0, 0.0), &Vector3::new(0.0, -1.0, 0.0)).unwrap();
+    let bottom_hit = raycaster.raycast(&Vector3::new(0.0, -3.0, 0.0), &Vector3::new(0.0, 1.0, 0.0)).unwrap();
+
+    let camera = Transform::default();
+    let top_color = material.compute_view_color(&top_hit, &camera).unwrap();
+    let bottom_color = material.compute_view_color(&bottom_hit, &camera).unwrap();
+    assert!(top_color.r > bottom_color.r);
+
+    // Turning the camera upside down puts the top side in the shade
+    let flipped_camera = Transform::new(&Vector3::new(0.0, 0.0, 0.0), &Vector3::new(0.0, 0.0, 180.0));
+    assert!(material.compute_view_color(&top_hit, &flipped_camera).unwrap().r < top_color.r);
+}
@@ -0,0 +1,37 @@
+use std::f32::consts::PI;
+
+use crate::rendering::medium::Medium;
+use crate::utils::approximately;
+
+#[test]
+fn test_isotropic_phase() {
+    let medium = Medium { anisotropy: 0.0, ..Default::default() };
+
+    assert!(approximately(medium.compute_phase(1.0), 1.0 / (4.0 * PI)));
+    assert!(approximately(medium.compute_phase(-1.0), 1.0 / (4.0 * PI)));
+}
+
+#[test]
+fn test_phase_normalization() {
+    let medium = Medium { anisotropy: 0.6, ..Default::default() };
+
+    // Integrate over the sphere, the phase depends only on the angle
+    let steps_count = 10000;
+    let integral: f32 = (0..steps_count)
+        .map(|i| {
+            let cos_angle = -1.0 + 2.0 * (i as f32 + 0.5) / steps_count as f32;
+            medium.compute_phase(cos_angle) * 2.0 * PI * (2.0 / steps_count as f32)
+        })
+        .sum();
+
+    assert!((integral - 1.0).abs() < 0.01);
+    assert!(medium.compute_phase(1.0) > medium.compute_phase(-1.0));
+}
+
+#[test]
+fn test_transmittance() {
+    let medium = Medium { density: 0.5, ..Default::default() };
+
+    assert!(approximately(medium.compute_transmittance(0.0), 1.0));
+    assert!(approximately(medium.compute_transmittance(2.0), (-1.0f32).exp()));
+}
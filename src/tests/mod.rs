@@ -1,3 +1,20 @@
 pub mod utils_tests;
 pub mod matrix_tests;
 pub mod color_tests;
+pub mod vector_tests;
+pub mod random_tests;
+pub mod light_tests;
+pub mod brdf_tests;
+pub mod raycaster_tests;
+pub mod lightning_tests;
+pub mod image_tests;
+pub mod environment_tests;
+pub mod background_tests;
+pub mod cubemap_tests;
+pub mod fog_tests;
+pub mod medium_tests;
+pub mod texture_tests;
+pub mod material_tests;
+pub mod noise_tests;
+pub mod scene_tests;
+pub mod material_graph_tests;
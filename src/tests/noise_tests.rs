@@ -0,0 +1,44 @@
+use crate::utils::noise::PerlinNoise;
+use crate::utils::random::Random;
+use crate::utils::vector::Vector3;
+
+#[test]
+fn test_perlin_noise() {
+    let noise = PerlinNoise::new(3);
+    let mut random = Random::new(11);
+
+    for _ in 0..1000 {
+        let p = Vector3::new(random.next_f32(), random.next_f32(), random.next_f32()) * 200.0 - Vector3::new(100.0, 100.0, 100.0);
+        let v = noise.sample(&p);
+
+        assert!((-1.0..=1.0).contains(&v));
+        // Noise is continuous across cell borders
+        assert!((noise.sample(&(p + Vector3::new(0.001, 0.0, 0.0))) - v).abs() < 0.01);
+    }
+
+    assert_eq!(noise.sample(&Vector3::new(3.0, -7.0, 12.0)), 0.0);
+    assert_ne!(noise.sample(&Vector3::new(0.5, 0.5, 0.5)), PerlinNoise::new(4).sample(&Vector3::new(0.5, 0.5, 0.5)));
+}
+
+#[test]
+fn test_fractal_noise_range() {
+    let noise = PerlinNoise::new(5);
+    let mut random = Random::new(13);
+
+    for _ in 0..1000 {
+        let p = Vector3::new(random.next_f32(), random.next_f32(), random.next_f32()) * 50.0;
+
+        assert!((-1.0..=1.0).contains(&noise.fbm(&p, 6)));
+        assert!((0.0..=1.0).contains(&noise.turbulence(&p, 6)));
+    }
+}
+
+#[test]
+fn test_noise_far_from_origin() {
+    let noise = PerlinNoise::new(7);
+
+    // Lattice repeats, so huge and infinite positions must not overflow the lattice coordinates
+    assert!((-1.0..=1.0).contains(&noise.sample(&Vector3::new(3.0e9, -5.0e9, 0.5))));
+    noise.sample(&Vector3::new(f32::INFINITY, f32::NEG_INFINITY, 0.0));
+    noise.cellular(&Vector3::new(f32::INFINITY, 1.0e10, -1.0e10));
+}
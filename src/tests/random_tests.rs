@@ -0,0 +1,24 @@
+use crate::utils::random::Random;
+use crate::utils::vector::Vector3;
+
+#[test]
+fn test_next_f32_range() {
+    let mut random = Random::new(42);
+
+    for _ in 0..1000 {
+        let v = random.next_f32();
+        assert!((0.0..1.0).contains(&v));
+    }
+}
+
+#[test]
+fn test_next_cosine_direction() {
+    let mut random = Random::new(7);
+    let normal = Vector3::new(-1.0, 2.0, 0.5).normalized();
+
+    for _ in 0..1000 {
+        let v = random.next_cosine_direction(&normal);
+        assert!((v.length() - 1.0).abs() < 0.001);
+        assert!(Vector3::dot(&v, &normal) >= 0.0);
+    }
+}
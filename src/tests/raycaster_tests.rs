@@ -0,0 +1,142 @@
+use crate::raycaster::{Raycaster, BoxFace, HitDifferentials, BoxRaycaster, SphereRaycaster};
+use crate::rendering::camera::Camera;
+use crate::utils::approximately;
+use crate::utils::random::Random;
+use crate::utils::vector::Vector3;
+
+#[test]
+fn test_box_surface_samples() {
+    let raycaster = BoxRaycaster::new(&Vector3::new(1.0, 2.0, 3.0));
+    let mut random = Random::new(3);
+
+    for _ in 0..1000 {
+        let sample = raycaster.sample_surface(&mut random);
+        let p = sample.position;
+
+        assert!(approximately(sample.area, 22.0));
+        assert!(p.x.abs() <= 0.5 && p.y.abs() <= 1.0 && p.z.abs() <= 1.5);
+        // Point lies on the face its normal points out of
+        assert!(approximately(Vector3::dot(&p, &sample.normal), Vector3::dot(&Vector3::new(0.5, 1.0, 1.5), &sample.normal.abs())));
+    }
+}
+
+#[test]
+fn test_sphere_surface_samples() {
+    let raycaster = SphereRaycaster::new(2.0);
+    let mut random = Random::new(5);
+
+    for _ in 0..1000 {
+        let sample = raycaster.sample_surface(&mut random);
+
+        assert!(approximately(sample.position.length(), 2.0));
+        assert!(sample.normal.approximately(&(sample.position * 0.5)));
+    }
+}
+
+#[test]
+fn test_hit_distance() {
+    let mut raycaster = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0));
+    raycaster.get_mut_tranform().set_position(&Vector3::new(0.0, 0.0, 1.0));
+
+    let hit = raycaster.raycast(&Vector3::new(0.0, 0.0, -5.0), &Vector3::new(0.0, 0.0, 1.0)).unwrap();
+    assert!(approximately(hit.distance, 5.5));
+}
+
+#[test]
+fn test_box_uv() {
+    let raycaster = BoxRaycaster::new(&Vector3::new(2.0, 2.0, 2.0));
+    let forward = Vector3::new(0.0, 0.0, 1.0);
+
+    let center = raycaster.raycast(&Vector3::new(0.0, 0.0, -5.0), &forward).unwrap();
+    assert!(approximately(center.uv.0, 0.5) && approximately(center.uv.1, 0.5));
+
+    // Front face is seen upright, with U growing to the right
+    let corner = raycaster.raycast(&Vector3::new(0.5, 0.5, -5.0), &forward).unwrap();
+    assert!(approximately(corner.uv.0, 0.75) && approximately(corner.uv.1, 0.75));
+}
+
+#[test]
+fn test_tangents_match_uv() {
+    let box_raycaster = BoxRaycaster::new(&Vector3::new(1.0, 2.0, 3.0));
+    let sphere_raycaster = SphereRaycaster::new(2.0);
+    let origin = Vector3::new(-4.0, 3.0, -5.0);
+    let step = 0.01;
+
+    for raycaster in [&box_raycaster as &dyn Raycaster, &sphere_raycaster] {
+        let hit = raycaster.raycast(&origin, &-origin).unwrap();
+
+        // Moving along a tangent changes only its own coordinate, by the length of the move
+        let moved_u = raycaster.raycast(&origin, &(hit.local_position + hit.dpdu * step - origin)).unwrap();
+        assert!(approximately(moved_u.uv.0 - hit.uv.0, step) && approximately(moved_u.uv.1, hit.uv.1));
+
+        let moved_v = raycaster.raycast(&origin, &(hit.local_position + hit.dpdv * step - origin)).unwrap();
+        assert!(approximately(moved_v.uv.0, hit.uv.0) && approximately(moved_v.uv.1 - hit.uv.1, step));
+    }
+}
+
+#[test]
+fn test_hit_differentials() {
+    let camera = Camera::default();
+    let mut raycaster = BoxRaycaster::new(&Vector3::new(2.0, 2.0, 2.0));
+    raycaster.get_mut_tranform().set_position(&Vector3::new(0.0, 0.0, 2.0));
+
+    let (origin, direction) = camera.get_ray_origin_direction(0.0, 0.0);
+    let ray = camera.get_ray_differentials(0.0, 0.0, 0.01, 0.01);
+
+    let hit = raycaster.raycast(&origin, &direction).unwrap();
+    let ray = ray.transfer(&direction.normalized(), hit.distance, &hit.normal);
+    let differentials = HitDifferentials::new(&hit, raycaster.get_tranform(), &ray.origin_dx, &ray.origin_dy);
+
+    // Face at distance 1 is seen with 60 degrees field of view, so a pixel spans 0.01 * tan(30) of it
+    let footprint = 0.01 * 30.0f32.to_radians().tan();
+    assert!(approximately(differentials.dpdx.x / 0.01, footprint / 0.01));
+    assert!(approximately(differentials.dpdy.y / 0.01, footprint / 0.01));
+    assert!(approximately(differentials.duvdx.0 / 0.01, footprint / 0.02));
+    assert!(approximately(differentials.duvdx.1, 0.0));
+}
+
+#[test]
+fn test_box_faces() {
+    let raycaster = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0));
+
+    for (i, face) in BoxFace::ALL.iter().enumerate() {
+        assert_eq!(face.get_index(), i);
+
+        // Shoot at the face from outside along its normal
+        let mut normal = [0.0; 3];
+        normal[i / 2] = if i % 2 == 0 { 1.0 } else { -1.0 };
+        let normal = Vector3::from(normal);
+
+        let hit = raycaster.raycast(&(normal * 3.0), &-normal).unwrap();
+        assert_eq!(hit.face, Some(*face));
+    }
+
+    let sphere_hit = SphereRaycaster::new(1.0).raycast(&Vector3::new(0.0, 0.0, -3.0), &Vector3::new(0.0, 0.0, 1.0));
+    assert_eq!(sphere_hit.unwrap().face, None);
+}
+
+#[test]
+fn test_tangent_frame() {
+    let sphere = SphereRaycaster::new(1.0);
+    let directions = [Vector3::new(0.3, 0.2, 1.0), Vector3::new(0.0, -1.0, 0.0)];
+
+    // Second ray hits the pole, where the UV derivatives vanish
+    for direction in directions {
+        let hit = sphere.raycast(&(-direction * 3.0), &direction.normalized()).unwrap();
+        let (tangent, bitangent) = hit.get_tangent_frame();
+
+        assert!(approximately(tangent.length(), 1.0));
+        assert!(approximately(bitangent.length(), 1.0));
+        assert!(approximately(Vector3::dot(&tangent, &bitangent), 0.0));
+        assert!(approximately(Vector3::dot(&tangent, &hit.local_normal), 0.0));
+        assert!(approximately(Vector3::dot(&bitangent, &hit.local_normal), 0.0));
+    }
+
+    // On box faces the frame follows the UV directions
+    let hit = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0))
+        .raycast(&Vector3::new(0.1, 0.2, -3.0), &Vector3::new(0.0, 0.0, 1.0))
+        .unwrap();
+    let (tangent, bitangent) = hit.get_tangent_frame();
+    assert!(tangent.approximately(&hit.dpdu.normalized()));
+    assert!(bitangent.approximately(&hit.dpdv.normalized()));
+}
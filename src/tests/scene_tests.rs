@@ -0,0 +1,39 @@
+use crate::raycaster::BoxRaycaster;
+use crate::rendering::material::{CheckerMaterial, CutoutMaterial, FlatMaterial, Reflectance};
+use crate::rendering::scene::{Scene, SceneObject};
+use crate::utils::color::Color;
+use crate::utils::vector::Vector3;
+
+#[test]
+fn test_occlusion_through_holes() {
+    // Box with checkered holes a quarter of a unit wide
+    let checker = CheckerMaterial {
+        scale: 0.5,
+        first_color: Color::new(255, 255, 255),
+        second_color: Color::new_with_alpha(0, 0, 0, 0),
+        reflectivity: 0.0,
+        reflectance: Reflectance::Diffuse
+    };
+    let material = CutoutMaterial { material: Box::new(checker), threshold: 0.5 };
+    let raycaster = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0));
+    let scene = Scene::new(vec![SceneObject::new(Box::new(raycaster), Box::new(material))]);
+    let direction = Vector3::new(0.0, 0.0, 1.0);
+
+    // Ray through holes of both the front and the back face
+    assert!(!scene.is_occluded(&Vector3::new(0.375, 0.125, -3.0), &direction, 10.0));
+    // Ray through a solid square of the front face
+    assert!(scene.is_occluded(&Vector3::new(0.125, 0.125, -3.0), &direction, 10.0));
+
+    // Slanted ray through a hole of the front face, blocked by the back face
+    let slanted_origin = Vector3::new(-0.75, 0.125, -3.0);
+    let slanted_direction = Vector3::new(0.25, 0.0, 1.0).normalized();
+    assert!(scene.is_occluded(&slanted_origin, &slanted_direction, 10.0));
+    // Back face further than the light does not occlude
+    assert!(!scene.is_occluded(&slanted_origin, &slanted_direction, 3.0));
+
+    // Mostly transparent surfaces cast no shadows
+    let glass = FlatMaterial { color: Color::new_with_alpha(255, 255, 255, 64), reflectivity: 0.0, reflectance: Reflectance::Diffuse };
+    let raycaster = BoxRaycaster::new(&Vector3::new(1.0, 1.0, 1.0));
+    let scene = Scene::new(vec![SceneObject::new(Box::new(raycaster), Box::new(glass))]);
+    assert!(!scene.is_occluded(&Vector3::new(0.0, 0.0, -3.0), &direction, 10.0));
+}
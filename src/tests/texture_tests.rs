@@ -0,0 +1,70 @@
+use crate::image::Image;
+use crate::rendering::texture::{Texture, TextureFilter, TextureWrap};
+use crate::utils::color::Color;
+
+/// 2x1 texture with a black texel on the left and a white one on the right.
+fn create_texture(filter: TextureFilter, wrap: TextureWrap) -> Texture {
+    let image = Image::new(2, 1, vec![Color::new(0, 0, 0), Color::new(255, 255, 255)]);
+    Texture::new(image).with_sampling(filter, wrap)
+}
+
+#[test]
+fn test_nearest_filter() {
+    let texture = create_texture(TextureFilter::Nearest, TextureWrap::Repeat);
+
+    assert_eq!(texture.sample(0.2, 0.5).r, 0);
+    assert_eq!(texture.sample(0.7, 0.5).r, 255);
+}
+
+#[test]
+fn test_bilinear_filter() {
+    let texture = create_texture(TextureFilter::Bilinear, TextureWrap::Clamp);
+
+    // Exactly between the texel centers
+    assert_eq!(texture.sample(0.5, 0.5).r, 128);
+    assert_eq!(texture.sample(0.25, 0.5).r, 0);
+    assert_eq!(texture.sample(0.0, 0.5).r, 0);
+}
+
+#[test]
+fn test_wrap_modes() {
+    let repeat = create_texture(TextureFilter::Nearest, TextureWrap::Repeat);
+    let clamp = create_texture(TextureFilter::Nearest, TextureWrap::Clamp);
+    let mirror = create_texture(TextureFilter::Nearest, TextureWrap::Mirror);
+
+    assert_eq!(repeat.sample(1.2, 0.5).r, 0);
+    assert_eq!(clamp.sample(1.2, 0.5).r, 255);
+    assert_eq!(mirror.sample(1.2, 0.5).r, 255);
+    assert_eq!(mirror.sample(-0.2, 0.5).r, 0);
+    assert_eq!(clamp.sample(-3.0, 0.5).r, 0);
+}
+
+#[test]
+fn test_mip_levels() {
+    // 4x2 image of black and white columns
+    let pixels = (0..8).map(|i| if i % 2 == 0 { Color::new(0, 0, 0) } else { Color::new(255, 255, 255) }).collect();
+    let texture = Texture::new(Image::new(4, 2, pixels));
+    assert_eq!(texture.get_levels_count(), 3);
+
+    // Footprint of one texel uses the full image, footprint of the whole image its average
+    assert_eq!(texture.sample_with_derivatives(0.125, 0.5, (0.25, 0.0), (0.0, 0.0)).r, 0);
+    assert_eq!(texture.sample_with_derivatives(0.125, 0.5, (1.0, 0.0), (0.0, 0.25)).r, 128);
+
+    let bilinear = texture.with_sampling(TextureFilter::Bilinear, TextureWrap::Repeat);
+    assert_eq!(bilinear.sample_with_derivatives(0.125, 0.5, (1.0, 0.0), (0.0, 0.25)).r, 0);
+}
+
+#[test]
+fn test_sample_far_coordinates() {
+    // Coordinates beyond the range of texel indices must not overflow them
+    for wrap in [TextureWrap::Repeat, TextureWrap::Clamp, TextureWrap::Mirror] {
+        for filter in [TextureFilter::Nearest, TextureFilter::Bilinear, TextureFilter::Trilinear] {
+            let texture = create_texture(filter, wrap);
+            texture.sample(f32::INFINITY, f32::NEG_INFINITY);
+            texture.sample(3.0e12, -3.0e12);
+            texture.sample_with_derivatives(f32::INFINITY, 0.5, (0.1, 0.0), (0.0, 0.1));
+        }
+    }
+
+    assert_eq!(create_texture(TextureFilter::Bilinear, TextureWrap::Clamp).sample(3.0e12, 0.5).r, 255);
+}
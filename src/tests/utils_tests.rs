@@ -1,4 +1,4 @@
-use crate::utils::{approximately, lerp, plane_cast};
+use crate::utils::{approximately, lerp, smoothstep, plane_cast, fresnel_schlick};
 use crate::utils::vector::Vector3;
 
 #[test]
@@ -25,6 +25,22 @@ fn test_lerp() {
     assert!(approximately(lerp(10.0, 20.0, 0.5), 15.0));
 }
 
+#[test]
+fn test_smoothstep() {
+    assert!(approximately(smoothstep(1.0, 3.0, 0.0), 0.0));
+    assert!(approximately(smoothstep(1.0, 3.0, 2.0), 0.5));
+    assert!(approximately(smoothstep(1.0, 3.0, 4.0), 1.0));
+    assert!(approximately(smoothstep(3.0, 1.0, 1.5), 0.84375));
+}
+
+#[test]
+fn test_fresnel_schlick() {
+    assert!(approximately(fresnel_schlick(1.0, 1.0, 1.5), 0.04));
+    assert!(approximately(fresnel_schlick(0.0, 1.0, 1.5), 1.0));
+    assert!(approximately(fresnel_schlick(1.0, 1.5, 1.0), 0.04));
+    assert!(fresnel_schlick(0.5, 1.0, 1.33) < fresnel_schlick(0.2, 1.0, 1.33));
+}
+
 #[test]
 fn test_plane_cast_non_none() {
     let n = Vector3::new(-1.0, 1.0, 0.5);
@@ -0,0 +1,30 @@
+use crate::utils::vector::{Vector3, UP_VECTOR};
+
+#[test]
+fn test_reflect() {
+    let v = Vector3::reflect(&Vector3::new(1.0, -1.0, 0.0), &UP_VECTOR);
+    assert!(v.approximately(&Vector3::new(1.0, 1.0, 0.0)));
+
+    let v = Vector3::reflect(&Vector3::new(0.0, 0.0, 1.0), &Vector3::new(0.0, 0.0, -1.0));
+    assert!(v.approximately(&Vector3::new(0.0, 0.0, -1.0)));
+}
+
+#[test]
+fn test_refract() {
+    let d = Vector3::new(1.0, -1.0, 0.0).normalized();
+
+    let v = Vector3::refract(&d, &UP_VECTOR, 1.0).unwrap();
+    assert!(v.approximately(&d));
+
+    // Snell's law: sin of the refracted angle is scaled by the ratio of indices
+    let v = Vector3::refract(&d, &UP_VECTOR, 1.0 / 1.5).unwrap();
+    assert!((v.x - d.x / 1.5).abs() < 0.0001);
+    assert!(v.y < 0.0);
+    assert!((v.length() - 1.0).abs() < 0.0001);
+}
+
+#[test]
+fn test_refract_total_internal_reflection() {
+    let d = Vector3::new(1.0, -0.2, 0.0).normalized();
+    assert!(Vector3::refract(&d, &UP_VECTOR, 1.5).is_none());
+}
@@ -1,7 +1,7 @@
 use imgui_glow_renderer::AutoRenderer as ImguiRenderer;
 use imgui_sdl2_support::SdlPlatform as ImguiPlatform;
 
-use crate::utils::vector::Vector3;
+use crate::utils::{vector::Vector3, color::Color};
 
 pub fn build_imgui(
     gl: glow::Context
@@ -39,3 +39,32 @@ pub fn drag_float3(ui: &imgui::Ui, label: &str, value: &mut Vector3) -> bool {
 
     return result;
 }
+
+/// Edit positions and colors of color ramp stops, with buttons to add and remove them.
+pub fn edit_color_ramp_stops(ui: &imgui::Ui, stops: &mut Vec<(f32, Color)>) -> bool {
+    let mut modified = false;
+    let mut removed_index = None;
+
+    for (i, (position, color)) in stops.iter_mut().enumerate() {
+        let _id = ui.push_id_usize(i);
+
+        modified |= ui.color_edit4(format!("Stop {}", i + 1), color);
+        modified |= ui.slider("Position", 0.0, 1.0, position);
+        ui.same_line();
+        if ui.button("Remove") {
+            removed_index = Some(i);
+        }
+    }
+
+    if let Some(i) = removed_index {
+        stops.remove(i);
+        modified = true;
+    }
+
+    if ui.button("Add stop") {
+        stops.push((0.5, Color::new(255, 255, 255)));
+        modified = true;
+    }
+
+    return modified;
+}
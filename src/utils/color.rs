@@ -1,3 +1,5 @@
+use std::ops;
+
 use super::lerp;
 
 pub type PixelData = u32;
@@ -38,6 +40,106 @@ impl Color {
     }
 }
 
+/// Color with unbounded floating point channels (1.0 matches 255)
+/// used to sum up light contributions.
+#[derive(Debug, PartialEq, Default, Clone, Copy)]
+pub struct HdrColor {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32
+}
+
+pub const BLACK_HDR_COLOR: HdrColor = HdrColor { r: 0.0, g: 0.0, b: 0.0 };
+pub const WHITE_HDR_COLOR: HdrColor = HdrColor { r: 1.0, g: 1.0, b: 1.0 };
+
+impl HdrColor {
+    pub fn new(r: f32, g: f32, b: f32) -> HdrColor {
+        HdrColor { r, g, b }
+    }
+
+    #[inline]
+    pub fn max_component(&self) -> f32 {
+        self.r.max(self.g).max(self.b)
+    }
+
+    #[inline]
+    pub fn lerp(a: &HdrColor, b: &HdrColor, t: f32) -> HdrColor {
+        HdrColor {
+            r: lerp(a.r, b.r, t),
+            g: lerp(a.g, b.g, t),
+            b: lerp(a.b, b.b, t)
+        }
+    }
+}
+
+impl ops::Add<HdrColor> for HdrColor {
+    type Output = HdrColor;
+
+    fn add(self, rhs: HdrColor) -> Self::Output {
+        HdrColor { r: self.r + rhs.r, g: self.g + rhs.g, b: self.b + rhs.b }
+    }
+}
+
+impl ops::AddAssign<HdrColor> for HdrColor {
+    fn add_assign(&mut self, rhs: HdrColor) {
+        self.r += rhs.r;
+        self.g += rhs.g;
+        self.b += rhs.b;
+    }
+}
+
+impl ops::Sub<HdrColor> for HdrColor {
+    type Output = HdrColor;
+
+    fn sub(self, rhs: HdrColor) -> Self::Output {
+        HdrColor { r: self.r - rhs.r, g: self.g - rhs.g, b: self.b - rhs.b }
+    }
+}
+
+impl ops::Mul<HdrColor> for HdrColor {
+    type Output = HdrColor;
+
+    fn mul(self, rhs: HdrColor) -> Self::Output {
+        HdrColor { r: self.r * rhs.r, g: self.g * rhs.g, b: self.b * rhs.b }
+    }
+}
+
+impl ops::MulAssign<HdrColor> for HdrColor {
+    fn mul_assign(&mut self, rhs: HdrColor) {
+        self.r *= rhs.r;
+        self.g *= rhs.g;
+        self.b *= rhs.b;
+    }
+}
+
+impl ops::Mul<f32> for HdrColor {
+    type Output = HdrColor;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        HdrColor { r: self.r * rhs, g: self.g * rhs, b: self.b * rhs }
+    }
+}
+
+impl From<Color> for HdrColor {
+    fn from(value: Color) -> Self {
+        HdrColor {
+            r: value.r as f32 / 255.0,
+            g: value.g as f32 / 255.0,
+            b: value.b as f32 / 255.0
+        }
+    }
+}
+
+impl From<HdrColor> for Color {
+    fn from(value: HdrColor) -> Self {
+        Color::new(
+            (value.r.clamp(0.0, 1.0) * 255.0) as u8,
+            (value.g.clamp(0.0, 1.0) * 255.0) as u8,
+            (value.b.clamp(0.0, 1.0) * 255.0) as u8
+        )
+    }
+}
+
 impl From<PixelData> for Color {
     fn from(value: PixelData) -> Self {
         Color {
@@ -79,3 +181,38 @@ impl From<Color> for mint::Vector4<f32> {
         }
     }
 }
+
+/// Gradient between colors placed at positions in range [0, 1].
+#[derive(Clone)]
+pub struct ColorRamp {
+    /// Positions and colors of the gradient stops, in any order.
+    pub stops: Vec<(f32, Color)>
+}
+
+impl ColorRamp {
+    pub fn new(stops: Vec<(f32, Color)>) -> ColorRamp {
+        ColorRamp { stops }
+    }
+
+    /// Check whether all colors of the ramp are fully opaque.
+    pub fn is_opaque(&self) -> bool {
+        !self.stops.is_empty() && self.stops.iter().all(|(_, color)| color.a == 255)
+    }
+
+    /// Get color at the position, interpolated between the closest stops on both sides.
+    /// Positions beyond the outermost stops get their colors.
+    pub fn sample(&self, t: f32) -> Color {
+        let below = self.stops.iter()
+            .filter(|(position, _)| *position <= t)
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        let above = self.stops.iter()
+            .filter(|(position, _)| *position > t)
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+
+        match (below, above) {
+            (Some((p0, c0)), Some((p1, c1))) => Color::lerp(c0, c1, (t - p0) / (p1 - p0)),
+            (Some((_, c)), None) | (None, Some((_, c))) => *c,
+            (None, None) => Color::default()
+        }
+    }
+}
@@ -4,6 +4,8 @@ pub mod vector;
 pub mod matrix;
 pub mod transform;
 pub mod color;
+pub mod random;
+pub mod noise;
 
 pub const EPSILON: f32 = 0.001;
 
@@ -17,6 +19,21 @@ pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
     (b - a) * t + a
 }
 
+/// Smooth Hermite step from 0 at `edge0` to 1 at `edge1`. Edges may go in either order.
+#[inline]
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Schlick's approximation of the Fresnel reflectance for light coming
+/// at angle with given cosine onto a boundary between two media.
+#[inline]
+pub fn fresnel_schlick(cos_theta: f32, ior_from: f32, ior_to: f32) -> f32 {
+    let r0 = ((ior_from - ior_to) / (ior_from + ior_to)).powi(2);
+    r0 + (1.0 - r0) * (1.0 - cos_theta).clamp(0.0, 1.0).powi(5)
+}
+
 #[inline]
 pub fn plane_cast(
     plane_normal: &Vector3, plane_d: f32, ray_origin: &Vector3, ray_direction: &Vector3
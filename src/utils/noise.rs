@@ -0,0 +1,144 @@
+use super::{lerp, random::Random, vector::Vector3};
+
+/// Frequency multiplier between successive octaves of fractal noise.
+const LACUNARITY: f32 = 2.0;
+/// Amplitude multiplier between successive octaves of fractal noise.
+const GAIN: f32 = 0.5;
+
+/// Perlin gradient noise in 3D, which is smooth and varies on the scale of one unit.
+#[derive(Clone)]
+pub struct PerlinNoise {
+    /// Shuffled numbers 0..256 repeated twice, so that hashes of neighbouring lattice points need no wrapping.
+    permutation: [u8; 512]
+}
+
+impl PerlinNoise {
+    pub fn new(seed: u32) -> PerlinNoise {
+        let mut values: [u8; 256] = std::array::from_fn(|i| i as u8);
+
+        let mut random = Random::new(seed);
+        for i in (1..values.len()).rev() {
+            let j = (random.next_u32() % (i as u32 + 1)) as usize;
+            values.swap(i, j);
+        }
+
+        PerlinNoise { permutation: std::array::from_fn(|i| values[i % 256]) }
+    }
+
+    /// Get noise value at the position, roughly in range [-1, 1]. It is zero at integer positions.
+    pub fn sample(&self, position: &Vector3) -> f32 {
+        let cell = [position.x.floor(), position.y.floor(), position.z.floor()];
+        let x = position.x - cell[0];
+        let y = position.y - cell[1];
+        let z = position.z - cell[2];
+
+        let hash = |di: f32, dj: f32, dk: f32| self.hash(cell[0] + di, cell[1] + dj, cell[2] + dk);
+
+        let u = fade(x);
+        let v = fade(y);
+        let w = fade(z);
+
+        // Blend gradients of the eight cell corners
+        let x00 = lerp(gradient(hash(0.0, 0.0, 0.0), x, y, z), gradient(hash(1.0, 0.0, 0.0), x - 1.0, y, z), u);
+        let x10 = lerp(
+            gradient(hash(0.0, 1.0, 0.0), x, y - 1.0, z), gradient(hash(1.0, 1.0, 0.0), x - 1.0, y - 1.0, z), u
+        );
+        let x01 = lerp(
+            gradient(hash(0.0, 0.0, 1.0), x, y, z - 1.0), gradient(hash(1.0, 0.0, 1.0), x - 1.0, y, z - 1.0), u
+        );
+        let x11 = lerp(
+            gradient(hash(0.0, 1.0, 1.0), x, y - 1.0, z - 1.0),
+            gradient(hash(1.0, 1.0, 1.0), x - 1.0, y - 1.0, z - 1.0),
+            u
+        );
+
+        return lerp(lerp(x00, x10, v), lerp(x01, x11, v), w);
+    }
+
+    /// Get fractal Brownian motion, the sum of noise octaves of rising frequency
+    /// and falling amplitude. Normalized to the same range as a single octave.
+    pub fn fbm(&self, position: &Vector3, octaves_count: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut total_amplitude = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+
+        for _ in 0..octaves_count.max(1) {
+            sum += self.sample(&(*position * frequency)) * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= GAIN;
+            frequency *= LACUNARITY;
+        }
+
+        return sum / total_amplitude;
+    }
+
+    /// Get turbulence, the fractal sum of absolute noise values, in range [0, 1].
+    /// Folding the noise makes sharp creases where it crosses zero.
+    pub fn turbulence(&self, position: &Vector3, octaves_count: u32) -> f32 {
+        let mut sum = 0.0;
+        let mut total_amplitude = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+
+        for _ in 0..octaves_count.max(1) {
+            sum += self.sample(&(*position * frequency)).abs() * amplitude;
+            total_amplitude += amplitude;
+            amplitude *= GAIN;
+            frequency *= LACUNARITY;
+        }
+
+        return (sum / total_amplitude).min(1.0);
+    }
+
+    /// Get distance to the closest of feature points scattered randomly, one in each unit cell.
+    /// Cellular (Worley) noise, which is zero at the feature points.
+    pub fn cellular(&self, position: &Vector3) -> f32 {
+        let cell = [position.x.floor(), position.y.floor(), position.z.floor()];
+        let mut min_sqr_distance = f32::INFINITY;
+
+        // Closest point is always in one of the neighbouring cells
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let corner = Vector3::new(cell[0] + dx as f32, cell[1] + dy as f32, cell[2] + dz as f32);
+                    let h = self.hash(corner.x, corner.y, corner.z) as usize;
+                    let offset = Vector3::new(
+                        h as f32, self.permutation[h] as f32, self.permutation[h + 1] as f32
+                    ) * (1.0 / 255.0);
+
+                    min_sqr_distance = min_sqr_distance.min((corner + offset - *position).sqr_length());
+                }
+            }
+        }
+
+        return min_sqr_distance.sqrt();
+    }
+
+    /// Get pseudo random byte of the lattice point with integer coordinates.
+    /// Lattice repeats every 256 units, wrapping the floats keeps huge and infinite coordinates in range.
+    fn hash(&self, x: f32, y: f32, z: f32) -> u8 {
+        let p = &self.permutation;
+        let i = x.rem_euclid(256.0) as usize;
+        let j = y.rem_euclid(256.0) as usize;
+        let k = z.rem_euclid(256.0) as usize;
+        return p[p[p[i] as usize + j] as usize + k];
+    }
+}
+
+/// Smooth the interpolation weight, so that the noise has continuous second derivatives.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// Get dot product of the offset from a lattice point with one of twelve gradients
+/// pointing to the edges of a cube, picked by the hash of the lattice point.
+fn gradient(hash: u8, x: f32, y: f32, z: f32) -> f32 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+
+    let u = if h & 1 == 0 { u } else { -u };
+    let v = if h & 2 == 0 { v } else { -v };
+    return u + v;
+}
@@ -0,0 +1,49 @@
+use std::f32::consts::PI;
+
+use super::vector::Vector3;
+
+/// Small and fast xorshift pseudo random number generator.
+pub struct Random {
+    state: u32
+}
+
+impl Random {
+    pub fn new(seed: u32) -> Random {
+        // Zero state would make xorshift produce only zeros
+        Random { state: if seed == 0 { 0x9E3779B9 } else { seed } }
+    }
+
+    #[inline]
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        return x;
+    }
+
+    /// Get uniformly distributed number in range [0, 1).
+    #[inline]
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Get direction in the hemisphere around `normal` with probability
+    /// density proportional to the cosine of angle with the normal.
+    pub fn next_cosine_direction(&mut self, normal: &Vector3) -> Vector3 {
+        let r = self.next_f32().sqrt();
+        let phi = 2.0 * PI * self.next_f32();
+
+        let (tangent, bitangent) = Vector3::orthonormal_basis(normal);
+        let z = (1.0 - r * r).max(0.0).sqrt();
+
+        return tangent * (r * phi.cos()) + bitangent * (r * phi.sin()) + *normal * z;
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Random::new(0x2545F491)
+    }
+}
@@ -2,7 +2,7 @@ use core::ops;
 
 use super::approximately;
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
 pub struct Vector3 {
     pub x: f32,
     pub y: f32,
@@ -34,6 +34,37 @@ impl Vector3 {
         }
     }
 
+    /// Mirror `direction` about the plane with given `normal`.
+    #[inline]
+    pub fn reflect(direction: &Vector3, normal: &Vector3) -> Vector3 {
+        *direction - *normal * (2.0 * Vector3::dot(direction, normal))
+    }
+
+    /// Bend normalized `direction` passing through the surface with given `normal`
+    /// (facing against the direction) according to Snell's law, where `eta` is
+    /// the ratio of refraction indices. Returns `None` on total internal reflection.
+    #[inline]
+    pub fn refract(direction: &Vector3, normal: &Vector3, eta: f32) -> Option<Vector3> {
+        let cos_i = -Vector3::dot(direction, normal);
+        let k = 1.0 - eta.powi(2) * (1.0 - cos_i.powi(2));
+
+        if k < 0.0 {
+            return None;
+        }
+
+        return Some(*direction * eta + *normal * (eta * cos_i - k.sqrt()));
+    }
+
+    /// Get two unit vectors that together with normalized `normal`
+    /// form an orthonormal basis.
+    #[inline]
+    pub fn orthonormal_basis(normal: &Vector3) -> (Vector3, Vector3) {
+        let helper = if normal.x.abs() > 0.9 { UP_VECTOR } else { RIGHT_VECTOR };
+        let tangent = Vector3::cross(&helper, normal).normalized();
+        let bitangent = Vector3::cross(normal, &tangent);
+        return (tangent, bitangent);
+    }
+
     #[inline]
     pub fn sqr_length(&self) -> f32 {
         self.x.powi(2) + self.y.powi(2) + self.z.powi(2)